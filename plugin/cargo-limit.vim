@@ -5,40 +5,107 @@ function! s:on_cargo_metadata(_job_id, data, event)
   if a:event == 'stdout'
     call add(s:data_chunks, join(a:data, ''))
   elseif a:event == 'stderr' && type(a:data) == v:t_list && a:data != ['']
-    let l:stderr = join(a:data, "\n")
-    if l:stderr !~ 'could not find `Cargo.toml`'
-      call s:log_error(l:stderr)
-    endif
+    call s:on_cargo_metadata_stderr(join(a:data, "\n"))
   elseif a:event == 'exit'
-    let l:stdout = join(s:data_chunks, '')
-    if len(l:stdout) > 0
-      let l:metadata = json_decode(l:stdout)
-      let l:workspace_root = get(l:metadata, 'workspace_root')
-      let l:escaped_workspace_root = substitute(workspace_root, '[/\\:]', '%', 'g')
+    call s:on_cargo_metadata_stdout(join(s:data_chunks, ''))
+  endif
+endfunction
+
+function! s:on_vim_cargo_metadata_stdout(_channel, data)
+  call add(s:data_chunks, a:data)
+endfunction
+
+function! s:on_vim_cargo_metadata_stderr(_channel, data)
+  call s:on_cargo_metadata_stderr(a:data)
+endfunction
+
+function! s:on_vim_cargo_metadata_close(_channel)
+  call s:on_cargo_metadata_stdout(join(s:data_chunks, ''))
+endfunction
+
+function! s:on_cargo_metadata_stderr(stderr)
+  if a:stderr !~ 'could not find `Cargo.toml`'
+    call s:log_error(a:stderr)
+  endif
+endfunction
+
+function! s:on_cargo_metadata_stdout(stdout)
+  if len(a:stdout) > 0
+    let l:metadata = json_decode(a:stdout)
+    let l:workspace_root = get(l:metadata, 'workspace_root')
+    let l:escaped_workspace_root = substitute(workspace_root, '[/\\:]', '%', 'g')
+    if has('nvim')
       let l:server_address = s:create_server_address(l:escaped_workspace_root)
       if !filereadable(l:server_address)
         call serverstart(l:server_address)
         call s:log_info('cargo-limit is ready')
       endif
+    elseif has('unix')
+      call s:start_vim_listener(l:escaped_workspace_root)
+    else
+      call s:start_vim_server(l:escaped_workspace_root)
     endif
   endif
 endfunction
 
+function! s:start_vim_listener(escaped_workspace_root)
+  let s:vim_listener = job_start(
+  \ ['_cargo-limit-open-in-vim', '--listen', a:escaped_workspace_root], {
+  \ 'out_cb': function('s:on_vim_listener_stdout'),
+  \ 'err_cb': function('s:on_vim_listener_stderr'),
+  \ })
+  if job_status(s:vim_listener) == 'run'
+    call s:log_info('cargo-limit is ready')
+  endif
+endfunction
+
+function! s:on_vim_listener_stdout(_channel, data)
+  call g:CargoLimitOpen(json_decode(a:data))
+endfunction
+
+function! s:on_vim_listener_stderr(_channel, data)
+  call s:log_error(a:data)
+endfunction
+
+function! s:start_vim_server(escaped_workspace_root)
+  let l:server_name = 'vim-cargo-limit-' . $USERNAME . '-' . a:escaped_workspace_root
+  if !has('clientserver')
+    call s:log_error('cargo-limit: vim is compiled without +clientserver')
+  elseif v:servername == ''
+    call remote_startserver(l:server_name)
+    call s:log_info('cargo-limit is ready')
+  elseif v:servername !=? l:server_name
+    call s:log_error('cargo-limit: server name is already set to ' . v:servername)
+  endif
+endfunction
+
 function! s:create_server_address(escaped_workspace_root)
   let l:prefix = 'nvim-cargo-limit-'
   if has('win32')
     return '\\.\pipe\' . l:prefix . $USERNAME . '-' . a:escaped_workspace_root
   elseif has('unix')
-    let l:server_address_dir =  '/tmp/' . l:prefix . $USER
+    let l:server_address_dir = s:runtime_dir() . '/' . l:prefix . s:user() . '/' . a:escaped_workspace_root
+    if !isdirectory(l:server_address_dir)
+      " single socket of older versions
+      call delete(l:server_address_dir)
+    endif
     call mkdir(l:server_address_dir, 'p', 0700)
-    let l:server_address = l:server_address_dir . '/' . a:escaped_workspace_root
-    call s:maybe_delete_dead_unix_socket(l:server_address)
+    let l:server_address = l:server_address_dir . '/' . getpid()
+    call delete(l:server_address)
     return l:server_address
   else
     throw 'unsupported OS'
   endif
 endfunction
 
+function! s:runtime_dir()
+  return empty($XDG_RUNTIME_DIR) ? '/tmp' : $XDG_RUNTIME_DIR
+endfunction
+
+function! s:user()
+  return empty($USER) ? luaeval('vim.loop.getuid()') : $USER
+endfunction
+
 function! s:on_buffer_changed()
   let l:current_file = s:current_file()
   if l:current_file != '' && filereadable(l:current_file)
@@ -136,28 +203,6 @@ function! s:compute_changed_line_numbers()
   return l:changed_line_numbers
 endfunction
 
-function! s:maybe_delete_dead_unix_socket(server_address)
-  const lsof_executable = 'lsof'
-  const lsof_command = lsof_executable . ' -U'
-  if filereadable(a:server_address)
-    call system('which ' . lsof_executable)
-    let l:lsof_is_installed = v:shell_error == 0
-    if l:lsof_is_installed
-      let l:lsof_stdout = system(lsof_command)
-      let l:lsof_succeed = v:shell_error == 0
-      if l:lsof_succeed
-        let l:socket_is_dead = stridx(l:lsof_stdout, a:server_address) == -1
-        if l:socket_is_dead
-          let l:ignore = luaeval('os.remove(_A)', a:server_address)
-          call s:log_info('removed dead socket ' . a:server_address)
-        endif
-      else
-        call s:log_error('failed to execute "' . lsof_command . '"')
-      endif
-    endif
-  endif
-endfunction
-
 function! s:current_file()
   return resolve(expand('%:p'))
 endfunction
@@ -210,8 +255,15 @@ if has('nvim')
   \ 'on_stderr': function('s:on_cargo_metadata'),
   \ 'on_exit': function('s:on_cargo_metadata'),
   \ })
+elseif v:version >= 802 && has('job') && has('channel')
+  call job_start(['cargo', 'metadata', '--quiet', '--format-version=1'], {
+  \ 'out_mode': 'raw',
+  \ 'out_cb': function('s:on_vim_cargo_metadata_stdout'),
+  \ 'err_cb': function('s:on_vim_cargo_metadata_stderr'),
+  \ 'close_cb': function('s:on_vim_cargo_metadata_close'),
+  \ })
 else
-  throw 'unsupported text editor'
+  throw 'unsupported text editor, expected nvim >=0.7.0 or vim >=8.2 with +job and +channel'
 endif
 
 " vim:shiftwidth=2 softtabstop=2 tabstop=2
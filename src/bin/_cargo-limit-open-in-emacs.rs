@@ -0,0 +1,170 @@
+use anyhow::Result;
+use cargo_limit::{
+    models::EditorData,
+    opener::{read_editor_data, run_editor_client},
+    NO_EXIT_CODE,
+};
+use cargo_metadata::diagnostic::DiagnosticLevel;
+use std::{
+    fmt::Write,
+    io,
+    io::Read,
+    process::{exit, Command, ExitStatus},
+};
+
+const BUFFER_NAME: &str = "*cargo-limit*";
+const EXPECTED_EVAL_RESULT: &str = "nil";
+const EMACS_IS_NOT_RUNNING_ERRORS: &[&str] = &[
+    "emacsclient: can't find socket",
+    "emacsclient: To start the server in Emacs",
+    "emacsclient: No socket or alternate editor",
+    "\t--socket-name",
+    "\t--server-file",
+    "\t--alternate-editor",
+];
+
+struct EmacsCommand {
+    expression: String,
+}
+
+impl EmacsCommand {
+    fn from_editor_data<R: Read>(input: R) -> Result<Option<Self>> {
+        let (_, editor_data) = read_editor_data(input)?;
+        if editor_data.locations().is_empty() {
+            return Ok(None);
+        }
+
+        let expression = format!(
+            "(let ((buffer (get-buffer-create {buffer_name}))) \
+               (with-current-buffer buffer \
+                 (let ((inhibit-read-only t)) \
+                   (erase-buffer) \
+                   (insert {errors})) \
+                 (compilation-mode) \
+                 (setq default-directory {workspace_root})) \
+               (setq next-error-last-buffer buffer) \
+               (first-error) \
+               nil)",
+            buffer_name = quote(BUFFER_NAME),
+            errors = quote(&compilation_errors(&editor_data)),
+            workspace_root = quote(&format!("{}/", editor_data.workspace_root().display())),
+        );
+
+        Ok(Some(Self { expression }))
+    }
+
+    fn run(self) -> Result<ExitStatus> {
+        let mut command = Command::new("emacsclient");
+        command.args(["--eval", &self.expression]);
+        run_editor_client(command, EXPECTED_EVAL_RESULT, EMACS_IS_NOT_RUNNING_ERRORS)
+    }
+}
+
+/// Lines in GNU format, which are recognized by `compilation-mode`.
+fn compilation_errors(editor_data: &EditorData) -> String {
+    let mut result = String::new();
+    for location in editor_data.locations() {
+        let level = match location.level() {
+            DiagnosticLevel::Ice | DiagnosticLevel::Error => "error",
+            DiagnosticLevel::Warning => "warning",
+            _ => "note",
+        };
+        let _ = writeln!(
+            result,
+            "{}:{}:{}: {level}: {}",
+            location.path().display(),
+            location.line(),
+            location.column(),
+            location.message().replace('\n', " "),
+        );
+    }
+    result
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn main() -> Result<()> {
+    let code = if let Some(emacs_command) = EmacsCommand::from_editor_data(&mut io::stdin())? {
+        emacs_command.run()?.code().unwrap_or(NO_EXIT_CODE)
+    } else {
+        0
+    };
+    exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use cargo_limit::opener::editor_is_not_running;
+
+    #[test]
+    fn expression() -> Result<()> {
+        let raw = r#"{
+            "protocol_version": "1.0",
+            "workspace_root": "/workspace",
+            "files": [
+                {
+                    "path": "/workspace/src/main.rs",
+                    "line": 5,
+                    "column": 3,
+                    "message": "cannot borrow `v`\nas mutable",
+                    "level": "error"
+                },
+                {
+                    "path": "/workspace/src/lib.rs",
+                    "line": 2,
+                    "column": 9,
+                    "message": "unused variable: \"x\"",
+                    "level": "warning"
+                }
+            ]
+        }"#;
+        let command = EmacsCommand::from_editor_data(raw.as_bytes())?.context("no command")?;
+        assert!(command
+            .expression
+            .starts_with(r#"(let ((buffer (get-buffer-create "*cargo-limit*")))"#));
+        assert!(command.expression.contains(
+            r#"(insert "/workspace/src/main.rs:5:3: error: cannot borrow `v` as mutable
+/workspace/src/lib.rs:2:9: warning: unused variable: \"x\"
+")"#
+        ));
+        assert!(command
+            .expression
+            .contains(r#"(setq default-directory "/workspace/")"#));
+
+        let raw = r#"{ "protocol_version": "1.0", "workspace_root": "/workspace", "files": [] }"#;
+        assert!(EmacsCommand::from_editor_data(raw.as_bytes())?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn emacs_is_not_running() {
+        let not_running = "emacsclient: can't find socket; have you started the server?\n\
+                           emacsclient: To start the server in Emacs, type \"M-x server-start\".\n\
+                           emacsclient: No socket or alternate editor.  Please use:\n\n\
+                           \t--socket-name\n\
+                           \t--server-file      (or environment variable EMACS_SERVER_FILE)\n\
+                           \t--alternate-editor (or environment variable ALTERNATE_EDITOR)\n";
+        assert!(editor_is_not_running(
+            not_running,
+            EMACS_IS_NOT_RUNNING_ERRORS
+        ));
+        assert!(!editor_is_not_running(
+            "emacsclient: error accessing socket \"/run/user/1000/emacs/server\"\n",
+            EMACS_IS_NOT_RUNNING_ERRORS
+        ));
+        assert!(!editor_is_not_running(
+            "*ERROR*: Symbol’s function definition is void: compilation-mode\n",
+            EMACS_IS_NOT_RUNNING_ERRORS
+        ));
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("*cargo-limit*"), "\"*cargo-limit*\"");
+        assert_eq!(quote(r#"C:\src "x""#), r#""C:\\src \"x\"""#);
+    }
+}
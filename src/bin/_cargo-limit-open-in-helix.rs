@@ -0,0 +1,198 @@
+use anyhow::Result;
+use cargo_limit::{models::Location, opener::read_editor_data, NO_EXIT_CODE};
+use std::{
+    env, io,
+    io::{Read, Write},
+    path::Path,
+    process::{exit, Command, ExitStatus},
+};
+
+const HELIX_EXECUTABLES: &[&str] = &["hx", "helix"];
+const TMUX_COMMANDS_DELIMITER: &str = ";";
+const SEND_KEYS_VAR: &str = "CARGO_HELIX_SEND_KEYS";
+
+/// Helix has no remote control, so keys are sent to its tmux pane.
+/// It interferes with whatever is being typed there, so it's enabled explicitly.
+struct HelixCommand {
+    pane: String,
+    keys: Vec<Keys>,
+}
+
+enum Keys {
+    Literal(String),
+    Named(&'static str),
+}
+
+impl HelixCommand {
+    fn from_editor_data<R: Read>(input: R) -> Result<Option<Self>> {
+        let (_, editor_data) = read_editor_data(input)?;
+        let Some(location) = editor_data.locations().first() else {
+            return Ok(None);
+        };
+        if !env::var(SEND_KEYS_VAR).map_or(false, |i| i == "true") {
+            let mut stderr = io::stderr();
+            writeln!(
+                stderr,
+                "{SEND_KEYS_VAR}=true is required to send keys to Helix tmux pane"
+            )?;
+            return Ok(None);
+        }
+        let Some(pane) = find_pane(editor_data.workspace_root())? else {
+            return Ok(None);
+        };
+        Ok(Some(Self::new(pane, location)))
+    }
+
+    fn new(pane: String, location: &Location) -> Self {
+        let path = location.path().to_string_lossy().replace('"', "\\\"");
+        let mut keys = vec![
+            Keys::Named("Escape"),
+            Keys::Literal(format!(":open \"{path}\"")),
+            Keys::Named("Enter"),
+            Keys::Literal(format!(":goto {}", location.line())),
+            Keys::Named("Enter"),
+            Keys::Literal("gh".to_owned()),
+        ];
+        if location.column() > 1 {
+            keys.push(Keys::Literal(format!("{}l", location.column() - 1)));
+        }
+        Self { pane, keys }
+    }
+
+    fn args(self) -> Vec<String> {
+        let mut args = Vec::new();
+        for keys in self.keys {
+            if !args.is_empty() {
+                args.push(TMUX_COMMANDS_DELIMITER.to_owned());
+            }
+            args.extend(["send-keys".to_owned(), "-t".to_owned(), self.pane.clone()]);
+            match keys {
+                Keys::Literal(text) => args.extend(["-l".to_owned(), text]),
+                Keys::Named(key) => args.push(key.to_owned()),
+            }
+        }
+        args
+    }
+
+    fn run(self) -> Result<ExitStatus> {
+        Ok(Command::new("tmux").args(self.args()).status()?)
+    }
+}
+
+/// Returns first tmux pane running Helix within the workspace.
+fn find_pane(workspace_root: &Path) -> Result<Option<String>> {
+    let output = match Command::new("tmux")
+        .args([
+            "list-panes",
+            "-a",
+            "-F",
+            "#{pane_id}\t#{pane_current_command}\t#{pane_current_path}",
+        ])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return Ok(None),
+    };
+    Ok(parse_pane(
+        &String::from_utf8_lossy(&output.stdout),
+        workspace_root,
+    ))
+}
+
+fn parse_pane(panes: &str, workspace_root: &Path) -> Option<String> {
+    panes
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some((fields.next()?, fields.next()?, fields.next()?))
+        })
+        .find(|(_, command, path)| {
+            HELIX_EXECUTABLES.contains(command) && Path::new(path).starts_with(workspace_root)
+        })
+        .map(|(pane, _, _)| pane.to_owned())
+}
+
+fn main() -> Result<()> {
+    let code = if let Some(helix_command) = HelixCommand::from_editor_data(&mut io::stdin())? {
+        helix_command.run()?.code().unwrap_or(NO_EXIT_CODE)
+    } else {
+        0
+    };
+    exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_keys() -> Result<()> {
+        let raw = r#"{
+            "protocol_version": "1.0",
+            "workspace_root": "/workspace",
+            "files": [{
+                "path": "/workspace/src/\"main\".rs",
+                "line": 5,
+                "column": 3,
+                "message": "error",
+                "level": "error"
+            }]
+        }"#;
+        let (_, editor_data) = read_editor_data(raw.as_bytes())?;
+        let command = HelixCommand::new("%1".to_owned(), &editor_data.locations()[0]);
+        assert_eq!(
+            command.args(),
+            [
+                "send-keys",
+                "-t",
+                "%1",
+                "Escape",
+                ";",
+                "send-keys",
+                "-t",
+                "%1",
+                "-l",
+                r#":open "/workspace/src/\"main\".rs""#,
+                ";",
+                "send-keys",
+                "-t",
+                "%1",
+                "Enter",
+                ";",
+                "send-keys",
+                "-t",
+                "%1",
+                "-l",
+                ":goto 5",
+                ";",
+                "send-keys",
+                "-t",
+                "%1",
+                "Enter",
+                ";",
+                "send-keys",
+                "-t",
+                "%1",
+                "-l",
+                "gh",
+                ";",
+                "send-keys",
+                "-t",
+                "%1",
+                "-l",
+                "2l",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pane() {
+        let panes = "%0\tzsh\t/workspace\n%1\thx\t/elsewhere\n%2\thx\t/workspace/src\n";
+        assert_eq!(
+            parse_pane(panes, Path::new("/workspace")),
+            Some("%2".to_owned())
+        );
+        assert_eq!(parse_pane(panes, Path::new("/other")), None);
+    }
+}
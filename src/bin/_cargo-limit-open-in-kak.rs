@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use cargo_limit::{models::Location, opener::read_editor_data, NO_EXIT_CODE};
+use std::{
+    env, io,
+    io::{Read, Write},
+    process::{exit, Command, ExitStatus, Stdio},
+};
+
+const FIRST_CLIENT: &str = r#"%sh{printf %s "${kak_client_list%% *}"}"#;
+const DEAD_SESSION_SUFFIX: &str = "(dead)";
+
+struct KakouneCommand {
+    session: String,
+    commands: String,
+}
+
+impl KakouneCommand {
+    fn from_editor_data<R: Read>(input: R) -> Result<Option<Self>> {
+        let (_, editor_data) = read_editor_data(input)?;
+        let Some(location) = editor_data.locations().first() else {
+            return Ok(None);
+        };
+        let workspace_name = editor_data
+            .workspace_root()
+            .file_name()
+            .map(|i| i.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let Some(session) = find_session(&workspace_name)? else {
+            return Ok(None);
+        };
+        let client = env::var("KAKOUNE_CLIENT").ok();
+        Ok(Some(Self::new(session, client.as_deref(), location)))
+    }
+
+    fn new(session: String, client: Option<&str>, location: &Location) -> Self {
+        let client = client.map_or_else(|| FIRST_CLIENT.to_owned(), quote);
+        let path = quote(&location.path().to_string_lossy());
+        let message = quote(location.message());
+        let commands = format!(
+            "evaluate-commands -client {client} -verbatim edit -existing {path} {} {}\n\
+             evaluate-commands -client {client} -verbatim echo {message}\n",
+            location.line(),
+            location.column(),
+        );
+        Self { session, commands }
+    }
+
+    fn run(self) -> Result<ExitStatus> {
+        let mut child = Command::new("kak")
+            .args(["-p", &self.session])
+            .stdin(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .context("no stdin")?
+            .write_all(self.commands.as_bytes())?;
+        Ok(child.wait()?)
+    }
+}
+
+/// Returns current session, session named after the workspace or the only running one.
+fn find_session(workspace_name: &str) -> Result<Option<String>> {
+    if let Ok(session) = env::var("KAKOUNE_SESSION") {
+        return Ok(Some(session));
+    }
+
+    let output = Command::new("kak").arg("-l").output()?;
+    let sessions = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|session| !session.is_empty() && !session.ends_with(DEAD_SESSION_SUFFIX))
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+
+    let result = if let Some(session) = sessions.iter().find(|i| *i == workspace_name) {
+        Some(session.clone())
+    } else if let [session] = sessions.as_slice() {
+        Some(session.clone())
+    } else {
+        None
+    };
+    Ok(result)
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn main() -> Result<()> {
+    let code = if let Some(kakoune_command) = KakouneCommand::from_editor_data(&mut io::stdin())? {
+        kakoune_command.run()?.code().unwrap_or(NO_EXIT_CODE)
+    } else {
+        0
+    };
+    exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands() -> Result<()> {
+        let raw = r#"{
+            "protocol_version": "1.0",
+            "workspace_root": "/workspace",
+            "files": [{
+                "path": "/workspace/src/main.rs",
+                "line": 5,
+                "column": 3,
+                "message": "cannot find value `x` in 'scope'",
+                "level": "error"
+            }]
+        }"#;
+        let (_, editor_data) = read_editor_data(raw.as_bytes())?;
+        let location = &editor_data.locations()[0];
+
+        let command = KakouneCommand::new("workspace".to_owned(), Some("client0"), location);
+        assert_eq!(command.session, "workspace");
+        assert_eq!(
+            command.commands,
+            "evaluate-commands -client 'client0' -verbatim edit -existing \
+             '/workspace/src/main.rs' 5 3\n\
+             evaluate-commands -client 'client0' -verbatim echo \
+             'cannot find value `x` in ''scope'''\n"
+        );
+
+        let command = KakouneCommand::new("workspace".to_owned(), None, location);
+        assert!(command.commands.starts_with(&format!(
+            "evaluate-commands -client {FIRST_CLIENT} -verbatim edit"
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("src/main.rs"), "'src/main.rs'");
+        assert_eq!(
+            quote("cannot find value `x` in 'scope'"),
+            "'cannot find value `x` in ''scope'''"
+        );
+    }
+}
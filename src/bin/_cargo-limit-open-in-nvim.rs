@@ -1,100 +1,244 @@
-use anyhow::{Error, Result};
-use cargo_limit::{models::EditorData, NO_EXIT_CODE};
+use anyhow::{bail, Context, Error, Result};
+use cargo_limit::{
+    models::EditorData,
+    opener::{
+        neovim::{discover, INSTANCES_VAR, MODES_VAR},
+        read_editor_data,
+    },
+    NO_EXIT_CODE,
+};
+use itertools::Itertools;
 use std::{
-    env, io,
-    io::{Read, Write},
-    process::{exit, Command, ExitStatus, Output},
+    env,
+    io::{self, Read},
+    process::exit,
+    str::FromStr,
 };
 
+const NVIM_PREFIX: &str = "nvim-cargo-limit-";
+
+const LATEST_INSTANCE: &str = "latest";
+const ALL_INSTANCES: &str = "all";
+const VALID_INSTANCES: &[&str] = &[LATEST_INSTANCE, ALL_INSTANCES];
+
+const TABS_MODE: &str = "tabs";
+const QUICKFIX_MODE: &str = "quickfix";
+const LOCLIST_MODE: &str = "loclist";
+const DIAGNOSTICS_MODE: &str = "diagnostics";
+const VALID_MODES: &[&str] = &[TABS_MODE, QUICKFIX_MODE, LOCLIST_MODE, DIAGNOSTICS_MODE];
+
+const QUICKFIX_ITEMS: &str = r#"
+local types = { error = "E", warning = "W", note = "N", help = "I" }
+local items = {}
+for _, location in ipairs(editor_data.files) do
+  table.insert(items, {
+    filename = location.path,
+    lnum = location.line,
+    col = location.column,
+    end_lnum = location.line_end,
+    end_col = location.column_end,
+    text = location.message,
+    type = types[location.level] or "E",
+  })
+end
+local what = { title = "cargo-limit", items = items }
+"#;
+
+const DIAGNOSTICS: &str = r#"
+local namespace = vim.api.nvim_create_namespace("cargo-limit")
+local severity = vim.diagnostic.severity
+local severities = { warning = severity.WARN, note = severity.INFO, help = severity.HINT }
+local diagnostics = {}
+vim.diagnostic.reset(namespace)
+for _, location in ipairs(editor_data.files) do
+  local buffer = vim.fn.bufadd(location.path)
+  local has_end = location.line_end > 0
+  diagnostics[buffer] = diagnostics[buffer] or {}
+  table.insert(diagnostics[buffer], {
+    lnum = location.line - 1,
+    col = location.column - 1,
+    end_lnum = (has_end and location.line_end or location.line) - 1,
+    end_col = (has_end and location.column_end or location.column) - 1,
+    severity = severities[location.level] or severity.ERROR,
+    message = location.message,
+    source = "rustc",
+    code = location.code,
+  })
+end
+for buffer, items in pairs(diagnostics) do
+  vim.diagnostic.set(namespace, buffer, items)
+end
+"#;
+
+/// How locations are delivered to Neovim.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    /// Passes them to `g:CargoLimitOpen`, which opens them in tabs by default.
+    Tabs,
+    Quickfix,
+    Loclist,
+    Diagnostics,
+}
+
+/// Which of running Neovim instances receive locations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Instances {
+    /// The one whose terminal runs cargo, or the most recently started one.
+    Latest,
+    All,
+}
+
 struct NeovimCommand {
-    escaped_workspace_root: String,
-    command: String,
+    editor_data: EditorData,
+    lua: String,
 }
 
 impl NeovimCommand {
-    fn from_editor_data<R: Read>(mut input: R) -> Result<Option<Self>> {
-        let mut raw_editor_data = String::new();
-        input.read_to_string(&mut raw_editor_data)?;
-        let command = format!("g:CargoLimitOpen({raw_editor_data})");
-
-        let editor_data: EditorData = serde_json::from_str(&raw_editor_data)?;
-        let escaped_workspace_root = editor_data.escaped_workspace_root();
-
-        Ok(Some(Self {
-            escaped_workspace_root,
-            command,
-        }))
+    fn from_editor_data<R: Read>(input: R, modes: &[Mode]) -> Result<Self> {
+        let (_, editor_data) = read_editor_data(input)?;
+        Ok(Self {
+            editor_data,
+            lua: lua(modes),
+        })
     }
 
-    fn run(self) -> Result<ExitStatus> {
-        let server_name = nvim_listen_address(self.escaped_workspace_root)?;
-        let remote_send_args = vec![
-            "--headless",
-            "--clean",
-            "--server",
-            &server_name,
-            "--remote-expr",
-            &self.command,
-        ];
-
-        match Command::new("nvim").args(remote_send_args).output() {
-            Ok(Output {
-                status,
-                stdout,
-                stderr,
-            }) => {
-                const EXPECTED_EXPR_RESULT: [u8; 1] = [b'0'];
-                if stdout != EXPECTED_EXPR_RESULT {
-                    let mut stdout_writer = io::stdout();
-                    stdout_writer.write_all(&stdout)?;
-                    stdout_writer.flush()?;
-                }
-
-                let failed_to_connect_is_the_only_error = stderr.starts_with(b"E247:")
-                    && stderr.iter().filter(|i| **i == b'\n').count() == 1;
-                if !failed_to_connect_is_the_only_error {
-                    let mut stderr_writer = io::stderr();
-                    stderr_writer.write_all(&stderr)?;
-                    stderr_writer.flush()?;
-                }
-
-                Ok(status)
-            },
-            Err(err) => Err(Error::from(err)),
+    /// Returns exit code, which isn't zero if any of instances has failed.
+    fn run(self, instances: Instances) -> Result<i32> {
+        let mut found_instances = discover(NVIM_PREFIX, self.editor_data.workspace_root())?;
+        if instances == Instances::Latest {
+            found_instances.truncate(1);
         }
+        let mut code = 0;
+        for mut instance in found_instances {
+            if let Err(err) = instance
+                .client
+                .exec_lua(&self.lua, &[&self.editor_data])
+                .with_context(|| format!("failed to open locations in {:?}", instance.address))
+            {
+                eprintln!("{err:#}");
+                code = NO_EXIT_CODE;
+            }
+        }
+        Ok(code)
     }
 }
 
-fn nvim_listen_address(escaped_workspace_root: String) -> Result<String> {
-    const PREFIX: &str = "nvim-cargo-limit-";
-
-    let result = {
-        #[cfg(unix)]
-        {
-            let user = env::var("USER")?;
-            format!("/tmp/{PREFIX}{user}/{escaped_workspace_root}")
+impl Mode {
+    fn from_env() -> Result<Vec<Self>> {
+        let modes = env::var(MODES_VAR).unwrap_or_default();
+        if modes.trim().is_empty() {
+            Ok(vec![Self::Tabs])
+        } else {
+            modes.split(',').map(|mode| mode.trim().parse()).collect()
         }
+    }
 
-        #[cfg(windows)]
-        {
-            let user = env::var("USERNAME")?;
-            format!(r"\\.\pipe\{PREFIX}{user}-{escaped_workspace_root}")
+    fn lua(self) -> &'static str {
+        match self {
+            Self::Tabs => "vim.fn.CargoLimitOpen(editor_data)",
+            Self::Quickfix => "vim.fn.setqflist({}, \" \", what)",
+            Self::Loclist => "vim.fn.setloclist(0, {}, \" \", what)",
+            Self::Diagnostics => DIAGNOSTICS,
         }
+    }
+}
 
-        #[cfg(not(any(unix, windows)))]
-        {
-            compile_error!("this platform is unsupported")
+impl FromStr for Mode {
+    type Err = Error;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode {
+            TABS_MODE => Ok(Self::Tabs),
+            QUICKFIX_MODE => Ok(Self::Quickfix),
+            LOCLIST_MODE => Ok(Self::Loclist),
+            DIAGNOSTICS_MODE => Ok(Self::Diagnostics),
+            _ => bail!(
+                "{MODES_VAR} must consist of {} (was {mode:?})",
+                VALID_MODES.join(", ")
+            ),
         }
+    }
+}
+
+/// Lua chunk which runs the modes one after another, with `EditorData` as argument.
+fn lua(modes: &[Mode]) -> String {
+    let needs_quickfix_items = modes
+        .iter()
+        .any(|mode| matches!(mode, Mode::Quickfix | Mode::Loclist));
+    let quickfix_items = if needs_quickfix_items {
+        QUICKFIX_ITEMS
+    } else {
+        ""
     };
+    let statements = modes.iter().map(|mode| mode.lua()).join("\n");
+    format!("local editor_data = ...\n{quickfix_items}\n{statements}\n")
+}
 
-    Ok(result)
+impl Instances {
+    fn from_env() -> Result<Self> {
+        let instances = env::var(INSTANCES_VAR).unwrap_or_default();
+        if instances.trim().is_empty() {
+            Ok(Self::Latest)
+        } else {
+            instances.trim().parse()
+        }
+    }
+}
+
+impl FromStr for Instances {
+    type Err = Error;
+
+    fn from_str(instances: &str) -> Result<Self> {
+        match instances {
+            LATEST_INSTANCE => Ok(Self::Latest),
+            ALL_INSTANCES => Ok(Self::All),
+            _ => bail!(
+                "{INSTANCES_VAR} must be {} (was {instances:?})",
+                VALID_INSTANCES.join(" or ")
+            ),
+        }
+    }
 }
 
 fn main() -> Result<()> {
-    let code = if let Some(neovim_command) = NeovimCommand::from_editor_data(&mut io::stdin())? {
-        neovim_command.run()?.code().unwrap_or(NO_EXIT_CODE)
-    } else {
-        0
-    };
+    let modes = Mode::from_env()?;
+    let instances = Instances::from_env()?;
+    let neovim_command = NeovimCommand::from_editor_data(&mut io::stdin(), &modes)?;
+    let code = neovim_command.run(instances)?;
     exit(code);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants() -> Result<()> {
+        assert_eq!("quickfix".parse::<Mode>()?, Mode::Quickfix);
+        assert_eq!("all".parse::<Instances>()?, Instances::All);
+        assert_eq!(
+            "first".parse::<Instances>().unwrap_err().to_string(),
+            "CARGO_NVIM_INSTANCES must be latest or all (was \"first\")"
+        );
+        assert_eq!(
+            "tab".parse::<Mode>().unwrap_err().to_string(),
+            "CARGO_NVIM_MODES must consist of tabs, quickfix, loclist, diagnostics (was \"tab\")"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lua_chunks() {
+        let chunk = lua(&[Mode::Tabs]);
+        assert_eq!(
+            chunk,
+            "local editor_data = ...\n\nvim.fn.CargoLimitOpen(editor_data)\n"
+        );
+
+        let chunk = lua(&[Mode::Diagnostics, Mode::Loclist]);
+        assert!(chunk.contains("local what = "));
+        assert!(chunk.contains("vim.diagnostic.set(namespace, buffer, items)"));
+        assert!(chunk.ends_with("vim.fn.setloclist(0, {}, \" \", what)\n"));
+        assert!(!chunk.contains("CargoLimitOpen"));
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use cargo_limit::opener::read_editor_data;
+use std::{
+    env,
+    io::{self, Read},
+    process::exit,
+};
+
+#[cfg(unix)]
+use {
+    anyhow::{bail, Context},
+    cargo_limit::opener::listen_address,
+    std::{
+        fs,
+        io::{ErrorKind, Write},
+        os::unix::{
+            fs::DirBuilderExt,
+            net::{UnixListener, UnixStream},
+        },
+        path::Path,
+        time::Duration,
+    },
+};
+
+#[cfg(not(unix))]
+use {
+    cargo_limit::{opener::run_remote_expr, NO_EXIT_CODE},
+    std::process::Command,
+};
+
+#[cfg(unix)]
+const LISTEN: &str = "--listen";
+
+/// Client which doesn't finish sending editor data in time shouldn't block the next ones.
+#[cfg(unix)]
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const PREFIX: &str = "vim-cargo-limit-";
+
+struct VimCommand {
+    escaped_workspace_root: String,
+    raw_editor_data: String,
+}
+
+impl VimCommand {
+    fn from_editor_data<R: Read>(input: R) -> Result<Self> {
+        let (raw_editor_data, editor_data) = read_editor_data(input)?;
+        let escaped_workspace_root = editor_data.escaped_workspace_root();
+        Ok(Self {
+            escaped_workspace_root,
+            raw_editor_data,
+        })
+    }
+
+    /// Sends editor data to the listener started by the plugin, if any.
+    #[cfg(unix)]
+    fn run(self) -> Result<i32> {
+        let address = vim_listen_address(&self.escaped_workspace_root)?;
+        match UnixStream::connect(address) {
+            Ok(mut stream) => {
+                stream.write_all(self.raw_editor_data.as_bytes())?;
+                Ok(0)
+            },
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorKind::NotFound | ErrorKind::ConnectionRefused
+                ) =>
+            {
+                Ok(0)
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Sends editor data to the server started by the plugin, if any.
+    #[cfg(not(unix))]
+    fn run(self) -> Result<i32> {
+        let user = env::var("USERNAME")?;
+        let server_name = format!("{PREFIX}{user}-{}", self.escaped_workspace_root);
+        let quoted_editor_data = self.raw_editor_data.replace('\'', "''");
+        let expr = format!("CargoLimitOpen(json_decode('{quoted_editor_data}'))");
+        let mut command = Command::new("vim");
+        command.args(["--servername", &server_name, "--remote-expr", &expr]);
+        let status = run_remote_expr(command)?;
+        Ok(status.code().unwrap_or(NO_EXIT_CODE))
+    }
+}
+
+/// Forwards editor data from the socket to stdout, which is read by the plugin's job callback.
+#[cfg(unix)]
+fn listen(escaped_workspace_root: &str) -> Result<()> {
+    let address = vim_listen_address(escaped_workspace_root)?;
+    if UnixStream::connect(&address).is_ok() {
+        bail!("{address:?} is controlled by another vim instance");
+    }
+    let _ = fs::remove_file(&address);
+    let listener =
+        UnixListener::bind(&address).with_context(|| format!("failed to bind {address:?}"))?;
+
+    let mut stdout = io::stdout();
+    for stream in listener.incoming() {
+        let line = stream.map_err(anyhow::Error::from).and_then(|mut stream| {
+            stream.set_read_timeout(Some(READ_TIMEOUT))?;
+            let mut raw_editor_data = String::new();
+            stream.read_to_string(&mut raw_editor_data)?;
+            let editor_data: serde_json::Value = serde_json::from_str(&raw_editor_data)?;
+            Ok(serde_json::to_string(&editor_data)? + "\n")
+        });
+        match line {
+            Ok(line) => {
+                if stdout
+                    .write_all(line.as_bytes())
+                    .and_then(|()| stdout.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            },
+            Err(err) => eprintln!("{err:#}"),
+        }
+    }
+
+    let _ = fs::remove_file(&address);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn vim_listen_address(escaped_workspace_root: &str) -> Result<String> {
+    let result = listen_address(PREFIX, escaped_workspace_root)?;
+    if let Some(dir) = Path::new(&result).parent() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    Ok(result)
+}
+
+fn main() -> Result<()> {
+    #[cfg(unix)]
+    if let [flag, escaped_workspace_root] = env::args().skip(1).collect::<Vec<_>>().as_slice() {
+        if flag == LISTEN {
+            listen(escaped_workspace_root)?;
+            return Ok(());
+        }
+    }
+
+    let vim_command = VimCommand::from_editor_data(&mut io::stdin())?;
+    let code = vim_command.run()?;
+    exit(code);
+}
@@ -0,0 +1 @@
+cargo_limit::run_subcommand!();
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const WORKSPACE_CONFIG_FILE_NAME: &str = ".cargo-limit.toml";
+const USER_CONFIG_DIR_NAME: &str = "cargo-limit";
+const USER_CONFIG_FILE_NAME: &str = "config.toml";
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub msg_limit: Option<usize>,
+    pub time_limit: Option<u64>,
+    pub int_grace: Option<u64>,
+    pub term_grace: Option<u64>,
+    pub error_limit: Option<usize>,
+    pub stop_on_failed_crates: Option<bool>,
+    pub stop_on_msg_limit: Option<bool>,
+    pub stream: Option<bool>,
+    pub stream_reprint: Option<bool>,
+    pub asc: Option<bool>,
+    pub force_warn: Option<bool>,
+    pub members_warn: Option<bool>,
+    pub deps_warn: Option<bool>,
+    pub remote_deps_warn: Option<bool>,
+    pub summary: Option<bool>,
+    pub progress: Option<bool>,
+    pub editor: Option<String>,
+    pub jump_to_related: Option<bool>,
+    pub report: Option<String>,
+    pub report_format: Option<String>,
+    pub ci_annotations: Option<String>,
+    pub publish_diagnostics: Option<bool>,
+    pub nvim_modes: Option<String>,
+    pub nvim_instances: Option<String>,
+    pub hide_lints: Option<Vec<String>>,
+    pub show_lints: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Returns existing configs, from the lowest priority to the highest one.
+    pub fn parse_all(workspace_root: &Path) -> Result<Vec<Self>> {
+        let user_config = match Self::user_config_path() {
+            Some(path) => Self::parse(&path)?,
+            None => None,
+        };
+        let workspace_config = Self::parse_workspace(workspace_root)?;
+        Ok(user_config.into_iter().chain(workspace_config).collect())
+    }
+
+    pub fn parse_workspace(workspace_root: &Path) -> Result<Option<Self>> {
+        Self::parse(&workspace_root.join(WORKSPACE_CONFIG_FILE_NAME))
+    }
+
+    fn parse(path: &Path) -> Result<Option<Self>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let raw_config =
+            fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+        let config = toml::from_str(&raw_config).with_context(|| format!("invalid {path:?}"))?;
+        Ok(Some(config))
+    }
+
+    fn user_config_path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|i| !i.is_empty())
+            .map(PathBuf::from)
+            .or_else(Self::platform_config_dir)?;
+        Some(
+            config_dir
+                .join(USER_CONFIG_DIR_NAME)
+                .join(USER_CONFIG_FILE_NAME),
+        )
+    }
+
+    #[cfg(windows)]
+    fn platform_config_dir() -> Option<PathBuf> {
+        env::var_os("APPDATA")
+            .filter(|i| !i.is_empty())
+            .map(PathBuf::from)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn platform_config_dir() -> Option<PathBuf> {
+        env::var_os("HOME").map(|home| Path::new(&home).join("Library/Application Support"))
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    fn platform_config_dir() -> Option<PathBuf> {
+        env::var_os("HOME").map(|home| Path::new(&home).join(".config"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn workspace_config() -> Result<()> {
+        let config = Config::parse_workspace(&stub_path("config"))?;
+        assert_eq!(
+            config,
+            Some(Config {
+                msg_limit: Some(3),
+                time_limit: Some(0),
+                asc: Some(true),
+                deps_warn: Some(true),
+                editor: Some("".to_owned()),
+                hide_lints: Some(vec![
+                    "dead_code".to_owned(),
+                    "clippy::needless_*".to_owned()
+                ]),
+                ..Config::default()
+            })
+        );
+
+        assert_eq!(Config::parse_workspace(&stub_path("minimal"))?, None);
+
+        let error = Config::parse_workspace(&stub_path("invalid_config"))
+            .expect_err("unknown key is accepted");
+        assert!(format!("{error:#}").contains("unknown field `message-limit`"));
+
+        Ok(())
+    }
+
+    fn stub_path(stub: &str) -> PathBuf {
+        Path::new("tests/stubs").join(stub)
+    }
+}
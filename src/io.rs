@@ -1,9 +1,13 @@
 use anyhow::{Context, Result};
+use cargo_metadata::Message;
 use std::{
-    io::{self, Write},
+    io::{self, BufRead, IsTerminal, Write},
     process::{Child, ChildStdout},
 };
 
+const CLEAR_TERMINAL: &[u8] = b"\x1b[2J\x1b[3J\x1b[H";
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
 #[derive(Clone)]
 pub struct FlushingWriter<W> {
     writer: W,
@@ -13,6 +17,7 @@ pub struct Buffers {
     child_stdout_reader: io::BufReader<ChildStdout>,
     stdout_writer: FlushingWriter<io::Stdout>,
     stderr_writer: FlushingWriter<io::Stderr>,
+    progress_shown: bool,
 }
 
 impl<W> FlushingWriter<W> {
@@ -43,33 +48,87 @@ impl Buffers {
             child_stdout_reader,
             stdout_writer,
             stderr_writer,
+            progress_shown: false,
         })
     }
 
-    pub fn map_child_stdout_reader<'this, T>(
-        &'this mut self,
-        f: impl FnOnce(&'this mut io::BufReader<ChildStdout>) -> T,
-    ) -> T {
-        f(&mut self.child_stdout_reader)
+    /// Reads one message at a time, so buffers could be written in between.
+    pub fn read_child_message(&mut self) -> io::Result<Option<Message>> {
+        let mut line = String::new();
+        if self.child_stdout_reader.read_line(&mut line)? == 0 {
+            Ok(None)
+        } else {
+            Message::parse_stream(line.as_bytes()).next().transpose()
+        }
+    }
+
+    /// Reads raw line including line ending, since app output is not necessarily UTF-8,
+    /// or a part of the line which is available without waiting for the rest of it.
+    pub fn read_child_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let available = self.child_stdout_reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(None);
+        }
+        let len = available
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(available.len(), |i| i + 1);
+        let chunk = available[..len].to_vec();
+        self.child_stdout_reader.consume(len);
+        Ok(Some(chunk))
     }
 
     pub fn write_to_stdout(&mut self, text: &str) -> io::Result<()> {
+        self.clear_progress()?;
         std::write!(&mut self.stdout_writer, "{}", text)
     }
 
     pub fn writeln_to_stdout(&mut self, text: &str) -> io::Result<()> {
+        self.clear_progress()?;
         std::writeln!(&mut self.stdout_writer, "{}", text)
     }
 
+    pub fn write_all_to_stdout(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.clear_progress()?;
+        self.stdout_writer.write_all(buf)
+    }
+
     pub fn write_to_stderr(&mut self, text: String) -> io::Result<()> {
+        self.clear_progress()?;
         std::write!(&mut self.stderr_writer, "{}", text)
     }
 
     pub fn write_all_to_stderr(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.clear_progress()?;
         self.stderr_writer.write_all(buf)
     }
 
+    /// Replaces the current progress line, it's cleared before anything else is written.
+    pub fn write_progress(&mut self, line: &str) -> io::Result<()> {
+        std::write!(&mut self.stderr_writer, "{CLEAR_LINE}{line}")?;
+        self.progress_shown = true;
+        Ok(())
+    }
+
+    pub fn clear_progress(&mut self) -> io::Result<()> {
+        if self.progress_shown {
+            std::write!(&mut self.stderr_writer, "{CLEAR_LINE}")?;
+            self.progress_shown = false;
+        }
+        Ok(())
+    }
+
     pub fn copy_from_child_stdout_reader_to_stdout_writer(&mut self) -> io::Result<u64> {
+        self.clear_progress()?;
         io::copy(&mut self.child_stdout_reader, &mut self.stdout_writer)
     }
 }
+
+pub fn clear_terminal() -> io::Result<()> {
+    let mut stderr_writer = io::stderr();
+    if stderr_writer.is_terminal() {
+        stderr_writer.write_all(CLEAR_TERMINAL)?;
+        stderr_writer.flush()?;
+    }
+    Ok(())
+}
@@ -3,83 +3,137 @@
 #[doc(hidden)]
 pub mod models;
 
+#[doc(hidden)]
+pub mod opener;
+
 mod cargo_toml;
+mod config;
 mod io;
+mod lints;
 mod messages;
 mod options;
 mod process;
+mod progress;
+mod publisher;
+mod report;
+mod test_failures;
+mod watch;
+mod workspace;
 
 #[doc(hidden)]
 pub use process::NO_EXIT_CODE;
 
-use crate::models::{EditorData, Location};
+use crate::{
+    models::{EditorData, Location, Report, Summary},
+    opener::neovim,
+};
 use anyhow::{Context, Result};
-use cargo_metadata::{Message, MetadataCommand};
+use cargo_metadata::Message;
 use io::Buffers;
-use messages::{transform_and_process_messages, Messages};
+use itertools::Either;
+use messages::{print_message, transform_and_process_messages, Messages, StreamedErrors};
 use options::Options;
 use process::{failed_to_execute_error_text, CargoProcess};
+use progress::Progress;
+use report::CiAnnotations;
 use std::{
     io::Write,
     path::Path,
     process::{Command, Stdio},
+    time::Instant,
 };
+use test_failures::TestFailures;
+use watch::Changes;
+use workspace::Workspace;
 
 const ADDITIONAL_ENVIRONMENT_VARIABLES: &str =
     include_str!("../additional_environment_variables.txt");
 
 #[doc(hidden)]
 pub fn run_cargo_filtered(current_exe: String) -> Result<i32> {
-    let workspace_root = MetadataCommand::new().no_deps().exec()?.workspace_root;
-    let workspace_root = workspace_root.as_ref();
-    let options = Options::from_os_env(current_exe, workspace_root)?;
+    let workspace = Workspace::from_metadata()?;
+    let options = Options::from_os_env(current_exe, workspace.root())?;
 
-    let mut cargo_process = CargoProcess::run(&options)?;
+    if options.watch && !options.help {
+        let changes = Changes::watch(&workspace);
+        loop {
+            io::clear_terminal()?;
+            let exit_code = run_cargo_filtered_once(&options, &workspace)?;
+            if !changes.wait() {
+                break Ok(exit_code);
+            }
+        }
+    } else {
+        run_cargo_filtered_once(&options, &workspace)
+    }
+}
+
+fn run_cargo_filtered_once(options: &Options, workspace: &Workspace) -> Result<i32> {
+    let workspace_root = workspace.root();
+    let started_at = Instant::now();
+    let ci_annotations = options.ci_annotations.detect(options.json_message_format);
+    let progress = Progress::new(options, workspace);
+    let mut cargo_process = CargoProcess::run(options)?;
     let mut buffers = cargo_process.buffers()?;
 
+    let mut processed_messages = None;
+    let mut locations_in_consistent_order = Vec::new();
     let process_messages = |buffers: &mut Buffers,
-                            messages: Vec<Message>,
-                            locations_in_consistent_order: Vec<Location>|
+                            messages_in_consistent_order: Vec<Message>,
+                            locations: Vec<Location>,
+                            summary: Summary,
+                            streamed_errors: StreamedErrors|
      -> Result<()> {
-        let messages = messages.into_iter();
-        if options.json_message_format {
-            for message in messages {
-                buffers.writeln_to_stdout(&serde_json::to_string(&message)?)?;
-            }
+        let messages = messages_in_consistent_order.iter();
+        let messages = if options.ascending_messages_order {
+            Either::Left(messages)
         } else {
-            for message in messages.filter_map(|message| match message {
-                Message::CompilerMessage(compiler_message) => compiler_message.message.rendered,
-                _ => None,
-            }) {
-                buffers.write_to_stderr(message)?;
+            Either::Right(messages.rev())
+        };
+        for message in messages {
+            let is_already_streamed = match message {
+                Message::CompilerMessage(compiler_message) => {
+                    !options.reprint_streamed_messages && streamed_errors.contains(compiler_message)
+                },
+                _ => false,
+            };
+            if !is_already_streamed {
+                print_message(buffers, message, options)?;
             }
         }
-        open_affected_files_in_external_app(
-            buffers,
-            locations_in_consistent_order,
-            &options,
-            workspace_root,
-        )
+        if options.show_summary && !summary.is_empty() {
+            buffers.write_to_stderr(format!("{summary}\n"))?;
+        }
+        if !options.report.is_empty() || ci_annotations != CiAnnotations::None {
+            processed_messages = Some((messages_in_consistent_order, summary));
+        }
+        locations_in_consistent_order = locations;
+        Ok(())
     };
 
     let mut parsed_messages =
-        Messages::parse_with_timeout_on_error(&mut buffers, Some(&cargo_process), &options)?;
+        Messages::parse_with_timeout_on_error(&mut buffers, &cargo_process, progress, options)?;
 
     let exit_code = if parsed_messages.child_killed {
         buffers.writeln_to_stdout("")?;
         let exit_code = cargo_process.wait()?;
-        parsed_messages.merge(Messages::parse_with_timeout_on_error(
-            &mut buffers,
-            None,
-            &options,
-        )?);
+        if let Some(stage) = cargo_process.stopped_by() {
+            buffers.write_to_stderr(format!("build stopped by {stage}\n"))?;
+        }
+        parsed_messages.parse_remaining(&mut buffers, options)?;
         transform_and_process_messages(
             &mut buffers,
             parsed_messages,
-            &options,
-            workspace_root,
+            options,
+            workspace,
             process_messages,
         )?;
+        process_locations(
+            &mut buffers,
+            workspace_root,
+            locations_in_consistent_order,
+            options,
+        )?;
         buffers.copy_from_child_stdout_reader_to_stdout_writer()?;
 
         exit_code
@@ -87,36 +141,94 @@ pub fn run_cargo_filtered(current_exe: String) -> Result<i32> {
         transform_and_process_messages(
             &mut buffers,
             parsed_messages,
-            &options,
-            workspace_root,
+            options,
+            workspace,
             process_messages,
         )?;
-        buffers.copy_from_child_stdout_reader_to_stdout_writer()?;
-        cargo_process.wait()?
+        if options.parse_test_failures {
+            let test_failures = TestFailures::parse(&mut buffers)?;
+            let exit_code = cargo_process.wait()?;
+            if !test_failures.is_empty() {
+                test_failures.print(&mut buffers, options)?;
+            }
+            let locations = test_failures
+                .locations(options, workspace_root)
+                .into_iter()
+                .chain(locations_in_consistent_order)
+                .collect();
+            process_locations(&mut buffers, workspace_root, locations, options)?;
+            exit_code
+        } else {
+            process_locations(
+                &mut buffers,
+                workspace_root,
+                locations_in_consistent_order,
+                options,
+            )?;
+            buffers.copy_from_child_stdout_reader_to_stdout_writer()?;
+            cargo_process.wait()?
+        }
     };
 
     if options.help {
         buffers.write_to_stdout(ADDITIONAL_ENVIRONMENT_VARIABLES)?;
     }
 
+    if let Some((messages, summary)) = processed_messages {
+        let report = Report::new(
+            workspace_root,
+            exit_code,
+            cargo_process.timed_out(),
+            started_at.elapsed(),
+            summary,
+            messages,
+        );
+        if !options.report.is_empty() {
+            options
+                .report_format
+                .write(Path::new(&options.report), &report)?;
+        }
+        ci_annotations.write(&mut buffers, &report)?;
+    }
+
     Ok(exit_code)
 }
 
-fn open_affected_files_in_external_app(
+fn process_locations(
     buffers: &mut Buffers,
+    workspace_root: &Path,
     locations_in_consistent_order: Vec<Location>,
     options: &Options,
-    workspace_root: &Path,
+) -> Result<()> {
+    let editor_data = EditorData::new(workspace_root, locations_in_consistent_order);
+    if options.publish_diagnostics {
+        publisher::publish(&editor_data)?;
+    }
+    open_affected_files_in_external_app(buffers, &editor_data, options)
+}
+
+fn open_affected_files_in_external_app(
+    buffers: &mut Buffers,
+    editor_data: &EditorData,
+    options: &Options,
 ) -> Result<()> {
     let app = &options.open_in_external_app;
     if !app.is_empty() {
-        let editor_data = EditorData::new(workspace_root, locations_in_consistent_order);
-        let mut child = Command::new(app).stdin(Stdio::piped()).spawn()?;
+        let mut command = Command::new(app);
+        for (var, value) in [
+            (neovim::MODES_VAR, &options.nvim_modes),
+            (neovim::INSTANCES_VAR, &options.nvim_instances),
+        ] {
+            if !value.is_empty() {
+                command.env(var, value);
+            }
+        }
+        let mut child = command.stdin(Stdio::piped()).spawn()?;
         child
             .stdin
             .take()
             .context("no stdin")?
-            .write_all(serde_json::to_string(&editor_data)?.as_bytes())?;
+            .write_all(serde_json::to_string(editor_data)?.as_bytes())?;
 
         let error_text = failed_to_execute_error_text(app);
         let output = child.wait_with_output().context(error_text)?;
@@ -0,0 +1,31 @@
+use glob::{Pattern, PatternError};
+use std::str::FromStr;
+
+const DELIMITER: char = ',';
+
+#[derive(Debug, Default, PartialEq)]
+pub struct LintPatterns(Vec<Pattern>);
+
+impl LintPatterns {
+    pub fn new<'i>(patterns: impl IntoIterator<Item = &'i str>) -> Result<Self, PatternError> {
+        let patterns = patterns
+            .into_iter()
+            .map(str::trim)
+            .filter(|i| !i.is_empty())
+            .map(Pattern::new)
+            .collect::<Result<_, _>>()?;
+        Ok(Self(patterns))
+    }
+
+    pub fn matches(&self, code: &str) -> bool {
+        self.0.iter().any(|pattern| pattern.matches(code))
+    }
+}
+
+impl FromStr for LintPatterns {
+    type Err = PatternError;
+
+    fn from_str(patterns: &str) -> Result<Self, Self::Err> {
+        Self::new(patterns.split(DELIMITER))
+    }
+}
@@ -1,65 +1,127 @@
-use crate::{io::Buffers, models::Location, options::Options, process};
+use crate::{
+    io::Buffers,
+    models::{project_primary_spans, project_secondary_spans, Location, Summary},
+    options::Options,
+    process,
+    progress::Progress,
+    workspace::{PackageKind, Workspace},
+};
 use anyhow::Result;
 use cargo_metadata::{
-    diagnostic::{DiagnosticLevel, DiagnosticSpan},
-    CompilerMessage, Message,
+    diagnostic::{Diagnostic, DiagnosticLevel, DiagnosticSpan},
+    CompilerMessage, Message, PackageId, Target,
 };
 use itertools::{Either, Itertools};
 use process::CargoProcess;
-use std::path::Path;
+use std::{collections::HashSet, iter, mem, path::Path};
+
+pub const CUSTOM_BUILD_KIND: &str = "custom-build";
 
 #[derive(Default, Debug)]
 pub struct Messages {
     internal_compiler_errors: Vec<CompilerMessage>,
     errors: Vec<CompilerMessage>,
     non_errors: Vec<CompilerMessage>,
+    distinct_errors: HashSet<Vec<DiagnosticSpan>>,
+    streamed_errors: StreamedErrors,
     pub child_killed: bool,
 }
 
+/// Errors which were printed while cargo was running.
+#[derive(Default, Debug)]
+pub struct StreamedErrors(HashSet<Vec<DiagnosticSpan>>);
+
+/// Abort triggers which don't depend on time, any of them stops cargo.
+#[derive(Default)]
+struct AbortTriggers {
+    failed_units: HashSet<(PackageId, Target)>,
+    unfinished_failed_units: HashSet<(PackageId, Target)>,
+    fired: bool,
+}
+
 struct FilteredAndOrderedMessages {
     errors: Vec<CompilerMessage>,
     warnings: Vec<CompilerMessage>,
+    summary: Summary,
 }
 
 struct TransformedMessages {
-    messages: Vec<Message>,
+    messages_in_consistent_order: Vec<Message>,
     locations_in_consistent_order: Vec<Location>,
+    summary: Summary,
 }
 
 pub fn transform_and_process_messages(
     buffers: &mut Buffers,
-    messages: Messages,
+    mut messages: Messages,
     options: &Options,
-    workspace_root: &Path,
-    mut process: impl FnMut(&mut Buffers, Vec<Message>, Vec<Location>) -> Result<()>,
+    workspace: &Workspace,
+    mut process: impl FnMut(
+        &mut Buffers,
+        Vec<Message>,
+        Vec<Location>,
+        Summary,
+        StreamedErrors,
+    ) -> Result<()>,
 ) -> Result<()> {
+    let streamed_errors = mem::take(&mut messages.streamed_errors);
     let TransformedMessages {
-        messages,
+        messages_in_consistent_order,
+        locations_in_consistent_order,
+        summary,
+    } = TransformedMessages::transform(messages, &streamed_errors, options, workspace)?;
+    process(
+        buffers,
+        messages_in_consistent_order,
         locations_in_consistent_order,
-    } = TransformedMessages::transform(messages, options, workspace_root)?;
-    process(buffers, messages, locations_in_consistent_order)
+        summary,
+        streamed_errors,
+    )
 }
 
 impl Messages {
     pub fn parse_with_timeout_on_error(
         buffers: &mut Buffers,
-        cargo_process: Option<&CargoProcess>,
+        cargo_process: &CargoProcess,
+        mut progress: Option<Progress>,
         options: &Options,
     ) -> Result<Self> {
         let mut result = Messages::default();
+        result.parse(buffers, Some(cargo_process), progress.as_mut(), options)?;
+        buffers.clear_progress()?;
+        Ok(result)
+    }
+
+    /// Parses messages which are left after the process is killed.
+    pub fn parse_remaining(&mut self, buffers: &mut Buffers, options: &Options) -> Result<()> {
+        self.parse(buffers, None, None, options)
+    }
+
+    fn parse(
+        &mut self,
+        buffers: &mut Buffers,
+        cargo_process: Option<&CargoProcess>,
+        mut progress: Option<&mut Progress>,
+        options: &Options,
+    ) -> Result<()> {
         if options.help || options.version {
-            return Ok(result);
+            return Ok(());
         }
 
-        for message in buffers.map_child_stdout_reader(Message::parse_stream) {
-            match message? {
+        let mut abort_triggers = AbortTriggers::default();
+        while let Some(message) = buffers.read_child_message()? {
+            abort_triggers.update(&message);
+            if let Some(progress) = progress.as_mut() {
+                progress.update(buffers, &message)?;
+            }
+            match message {
                 Message::CompilerMessage(compiler_message) => {
-                    match compiler_message.message.level {
-                        DiagnosticLevel::Ice => {
-                            result.internal_compiler_errors.push(compiler_message)
-                        },
-                        DiagnosticLevel::Error => result.errors.push(compiler_message),
-                        _ => result.non_errors.push(compiler_message),
+                    if self.add(compiler_message.clone(), options) {
+                        print_message(
+                            buffers,
+                            &Message::CompilerMessage(compiler_message),
+                            options,
+                        )?;
                     }
                 },
                 Message::BuildFinished(_) => {
@@ -69,29 +131,54 @@ impl Messages {
             }
 
             if let Some(cargo_process) = cargo_process {
-                if result.has_errors() {
+                if self.has_errors() {
                     if let Some(time_limit) = options.time_limit_after_error {
                         cargo_process.kill_after_timeout(time_limit);
                     }
                 }
+                if abort_triggers.fire(options, self.distinct_errors.len()) {
+                    cargo_process.kill_now();
+                }
             }
         }
 
-        result.child_killed = if let Some(cargo_process) = cargo_process {
-            cargo_process.wait_if_killing_is_in_progress() == process::State::NotRunning
-        } else {
-            false
-        };
+        if let Some(cargo_process) = cargo_process {
+            self.child_killed =
+                cargo_process.wait_if_killing_is_in_progress() == process::State::NotRunning;
+        }
 
-        Ok(result)
+        Ok(())
     }
 
-    pub fn merge(&mut self, other: Self) {
-        self.internal_compiler_errors
-            .extend(other.internal_compiler_errors);
-        self.errors.extend(other.errors);
-        self.non_errors.extend(other.non_errors);
-        self.child_killed |= other.child_killed;
+    /// Returns `true` if the message should be streamed right away.
+    ///
+    /// Errors are streamed until there are `limit_messages` of them,
+    /// so the rest of them isn't shown in the end either.
+    fn add(&mut self, compiler_message: CompilerMessage, options: &Options) -> bool {
+        let stream = self.add_distinct_error(&compiler_message, options)
+            && is_streamed(&compiler_message, options)
+            && (options.limit_messages == 0
+                || self.distinct_errors.len() <= options.limit_messages);
+        if stream {
+            self.streamed_errors.insert(&compiler_message);
+        }
+
+        match compiler_message.message.level {
+            DiagnosticLevel::Ice => self.internal_compiler_errors.push(compiler_message),
+            DiagnosticLevel::Error => self.errors.push(compiler_message),
+            _ => self.non_errors.push(compiler_message),
+        }
+        stream
+    }
+
+    /// Returns `true` if it's a shown error which wasn't seen before.
+    fn add_distinct_error(&mut self, message: &CompilerMessage, options: &Options) -> bool {
+        is_error(message)
+            && !message.message.spans.is_empty()
+            && is_shown_lint(message, options)
+            && self
+                .distinct_errors
+                .insert(FilteredAndOrderedMessages::dedup_key(message))
     }
 
     fn has_errors(&self) -> bool {
@@ -99,29 +186,139 @@ impl Messages {
     }
 }
 
+impl StreamedErrors {
+    fn insert(&mut self, message: &CompilerMessage) {
+        self.0
+            .insert(FilteredAndOrderedMessages::dedup_key(message));
+    }
+
+    pub fn contains(&self, message: &CompilerMessage) -> bool {
+        self.0
+            .contains(&FilteredAndOrderedMessages::dedup_key(message))
+    }
+}
+
+impl AbortTriggers {
+    fn update(&mut self, message: &Message) {
+        match message {
+            Message::CompilerMessage(compiler_message) => {
+                let diagnostic = &compiler_message.message;
+                if is_error(compiler_message) {
+                    let unit = (
+                        compiler_message.package_id.clone(),
+                        compiler_message.target.clone(),
+                    );
+                    let is_new = self.failed_units.insert(unit.clone());
+                    // Failed unit has no artifact, but rustc gives up on it
+                    // with an error without spans, like the final summary
+                    if diagnostic.spans.is_empty() {
+                        self.unfinished_failed_units.remove(&unit);
+                    } else if is_new {
+                        self.unfinished_failed_units.insert(unit);
+                    }
+                }
+            },
+            Message::CompilerArtifact(artifact) => {
+                self.unfinished_failed_units
+                    .remove(&(artifact.package_id.clone(), artifact.target.clone()));
+            },
+            Message::BuildScriptExecuted(build_script) => {
+                self.unfinished_failed_units.retain(|(package_id, target)| {
+                    *package_id != build_script.package_id
+                        || !target.kind.iter().any(|kind| kind == CUSTOM_BUILD_KIND)
+                });
+            },
+            _ => (),
+        }
+    }
+
+    /// Returns `true` only once, when any of enabled triggers is hit.
+    fn fire(&mut self, options: &Options, errors: usize) -> bool {
+        let error_limit_reached = options.error_limit > 0 && errors >= options.error_limit;
+        let messages_limit_reached = options.stop_on_messages_limit
+            && options.limit_messages > 0
+            && errors >= options.limit_messages;
+        let failed_crates_finished = options.stop_on_failed_crates
+            && !self.failed_units.is_empty()
+            && self.unfinished_failed_units.is_empty();
+
+        let hit = error_limit_reached || messages_limit_reached || failed_crates_finished;
+        let fire = hit && !self.fired;
+        self.fired |= hit;
+        fire
+    }
+}
+
 impl FilteredAndOrderedMessages {
-    fn filter(messages: Messages, options: &Options, workspace_root: &Path) -> Self {
-        let non_errors = messages.non_errors.into_iter();
-        let warnings = if options.show_dependencies_warnings {
-            Either::Left(non_errors)
-        } else {
-            Either::Right(non_errors.filter(|i| i.target.src_path.starts_with(workspace_root)))
+    fn filter(messages: Messages, options: &Options, workspace: &Workspace) -> Self {
+        let has_spans = |i: &CompilerMessage| !i.message.spans.is_empty();
+        let is_shown_lint = |i: &CompilerMessage| is_shown_lint(i, options);
+        let mut summary = Summary {
+            total_errors: messages
+                .internal_compiler_errors
+                .iter()
+                .chain(&messages.errors)
+                .filter(|i| has_spans(i))
+                .count(),
+            total_warnings: messages.non_errors.iter().filter(|i| has_spans(i)).count(),
+            ..Summary::default()
         };
-        let warnings = Self::filter_and_order_messages(warnings, workspace_root);
 
-        let cargo_errors = Self::filter_cargo_errors(&messages.errors);
+        let (warnings, hidden_lints_warnings): (Vec<_>, Vec<_>) =
+            messages.non_errors.into_iter().partition(is_shown_lint);
+        let (warnings, hidden_packages_warnings): (Vec<_>, Vec<_>) = warnings
+            .into_iter()
+            .partition(|i| match workspace.package_kind(&i.package_id) {
+                PackageKind::Member => options.show_members_warnings,
+                PackageKind::PathDependency => options.show_dependencies_warnings,
+                PackageKind::RemoteDependency => options.show_remote_dependencies_warnings,
+            });
+        let (hidden_members_warnings, hidden_dependencies_warnings): (Vec<_>, Vec<_>) =
+            hidden_packages_warnings
+                .into_iter()
+                .partition(|i| workspace.package_kind(&i.package_id) == PackageKind::Member);
+        let warnings_with_spans = warnings.iter().filter(|i| has_spans(i)).count();
+        let warnings = Self::filter_and_order_messages(warnings, workspace);
+
+        let (errors, hidden_lints_errors): (Vec<_>, Vec<_>) =
+            messages.errors.into_iter().partition(is_shown_lint);
+        let cargo_errors = Self::filter_cargo_errors(&errors);
         let errors = messages
             .internal_compiler_errors
             .into_iter()
-            .chain(messages.errors);
-        let errors = Self::filter_and_order_messages(errors, workspace_root);
+            .chain(errors)
+            .collect::<Vec<_>>();
+        let errors_with_spans = errors.iter().filter(|i| has_spans(i)).count();
+        let errors = Self::filter_and_order_messages(errors, workspace);
+        summary.duplicates =
+            errors_with_spans + warnings_with_spans - errors.len() - warnings.len();
         let errors = if errors.is_empty() {
             cargo_errors
         } else {
             errors
         };
 
-        Self { errors, warnings }
+        summary.hidden_lints = [&hidden_lints_warnings, &hidden_lints_errors]
+            .into_iter()
+            .flatten()
+            .filter(|i| has_spans(i))
+            .count();
+        summary.hidden_members_warnings = hidden_members_warnings
+            .iter()
+            .filter(|i| has_spans(i))
+            .count();
+        summary.hidden_dependencies_warnings = hidden_dependencies_warnings
+            .iter()
+            .filter(|i| has_spans(i))
+            .count();
+        summary.unique_errors = errors.len();
+        summary.unique_warnings = warnings.len();
+
+        Self {
+            errors,
+            warnings,
+            summary,
+        }
     }
 
     fn filter_cargo_errors(messages: &[CompilerMessage]) -> Vec<CompilerMessage> {
@@ -161,7 +358,7 @@ impl FilteredAndOrderedMessages {
 
     fn filter_and_order_messages(
         messages: impl IntoIterator<Item = CompilerMessage>,
-        workspace_root: &Path,
+        workspace: &Workspace,
     ) -> Vec<CompilerMessage> {
         let messages = messages
             .into_iter()
@@ -181,7 +378,7 @@ impl FilteredAndOrderedMessages {
         let mut project_messages = Vec::new();
         let mut dependencies_messages = Vec::new();
         for i in messages {
-            if i.target.src_path.starts_with(workspace_root) {
+            if workspace.package_kind(&i.package_id) == PackageKind::Member {
                 project_messages.push(i);
             } else {
                 dependencies_messages.push(i);
@@ -191,36 +388,57 @@ impl FilteredAndOrderedMessages {
         project_messages
             .into_iter()
             .chain(dependencies_messages)
-            .unique_by(|i| {
-                i.message
-                    .spans
-                    .clone()
-                    .into_iter()
-                    .unique_by(|span| {
-                        (
-                            span.line_start,
-                            span.text
-                                .iter()
-                                .map(|text| text.text.clone())
-                                .unique()
-                                .next(),
-                        )
-                    })
-                    .collect_vec()
-            })
+            .unique_by(Self::dedup_key)
             .collect()
     }
+
+    /// Messages with equal keys are shown once.
+    fn dedup_key(message: &CompilerMessage) -> Vec<DiagnosticSpan> {
+        message
+            .message
+            .spans
+            .clone()
+            .into_iter()
+            .unique_by(|span| {
+                (
+                    span.line_start,
+                    span.text
+                        .iter()
+                        .map(|text| text.text.clone())
+                        .unique()
+                        .next(),
+                )
+            })
+            .collect_vec()
+    }
 }
 
 impl TransformedMessages {
     fn transform(
         messages: Messages,
+        streamed_errors: &StreamedErrors,
         options: &Options,
-        workspace_root: &Path,
+        workspace: &Workspace,
     ) -> Result<TransformedMessages> {
-        let FilteredAndOrderedMessages { errors, warnings } =
-            FilteredAndOrderedMessages::filter(messages, options, workspace_root);
+        let FilteredAndOrderedMessages {
+            errors,
+            warnings,
+            mut summary,
+        } = FilteredAndOrderedMessages::filter(messages, options, workspace);
+        let limit_messages = options.limit_messages;
+        let no_limit = limit_messages == 0;
+        let errors = if options.stream_messages && !no_limit {
+            errors
+                .into_iter()
+                .filter(|i| i.message.spans.is_empty() || streamed_errors.contains(i))
+                .collect()
+        } else {
+            errors
+        };
         let has_errors = !errors.is_empty();
+        if has_errors && !options.show_warnings_if_errors_exist {
+            summary.suppressed_warnings = warnings.len();
+        }
 
         let errors = errors.into_iter();
         let warnings = warnings.into_iter();
@@ -235,8 +453,6 @@ impl TransformedMessages {
             Either::Right(messages)
         };
 
-        let limit_messages = options.limit_messages;
-        let no_limit = limit_messages == 0;
         let messages = {
             if no_limit {
                 Either::Left(messages)
@@ -246,23 +462,21 @@ impl TransformedMessages {
         }
         .collect::<Vec<_>>();
 
-        let locations_in_consistent_order =
-            Self::extract_locations_for_external_app(&messages, options, workspace_root);
+        summary.shown_errors = messages.iter().filter(|i| is_error(i)).count();
+        summary.shown_warnings = messages.len() - summary.shown_errors;
+        summary.truncated_errors = summary.unique_errors - summary.shown_errors;
+        summary.truncated_warnings =
+            summary.unique_warnings - summary.suppressed_warnings - summary.shown_warnings;
 
-        let messages = messages.into_iter();
-        let messages = {
-            if options.ascending_messages_order {
-                Either::Left(messages)
-            } else {
-                Either::Right(messages.rev())
-            }
-        }
-        .map(Message::CompilerMessage)
-        .collect();
+        let locations_in_consistent_order =
+            Self::extract_locations_for_external_app(&messages, options, workspace.root());
+        let messages_in_consistent_order =
+            messages.into_iter().map(Message::CompilerMessage).collect();
 
         Ok(TransformedMessages {
-            messages,
+            messages_in_consistent_order,
             locations_in_consistent_order,
+            summary,
         })
     }
 
@@ -273,37 +487,211 @@ impl TransformedMessages {
     ) -> Vec<Location> {
         messages
             .iter()
-            .filter(|message| {
-                if options.open_in_external_app_on_warnings {
-                    true
-                } else {
-                    matches!(
-                        message.message.level,
-                        DiagnosticLevel::Error | DiagnosticLevel::Ice
-                    )
-                }
-            })
+            .filter(|message| options.open_in_external_app_on_warnings || is_error(message))
             .flat_map(|message| {
-                message
-                    .message
-                    .spans
-                    .iter()
-                    .filter(|span| span.is_primary)
-                    .cloned()
-                    .map(move |span| (span, message))
+                let diagnostic = &message.message;
+                let related_spans = if options.jump_to_related_locations {
+                    Either::Left(project_secondary_spans(diagnostic))
+                } else {
+                    Either::Right(iter::empty())
+                };
+                project_primary_spans(diagnostic)
+                    .chain(related_spans)
+                    .map(move |span| Location::new(span, diagnostic, workspace_root))
             })
-            .map(|(span, message)| (Self::find_leaf_project_expansion(span), &message.message))
-            .filter(|(span, _)| Path::new(&span.file_name).is_relative())
-            .map(|(span, message)| Location::new(span, message, workspace_root))
             .collect()
     }
+}
 
-    fn find_leaf_project_expansion(mut span: DiagnosticSpan) -> DiagnosticSpan {
-        let mut project_span = span.clone();
-        while let Some(expansion) = span.expansion {
-            span = expansion.span;
-            project_span = span.clone();
-        }
-        project_span
+/// Prints the message the same way as cargo would.
+pub fn print_message(buffers: &mut Buffers, message: &Message, options: &Options) -> Result<()> {
+    if options.json_message_format {
+        buffers.writeln_to_stdout(&serde_json::to_string(message)?)?;
+    } else if let Message::CompilerMessage(CompilerMessage {
+        message: Diagnostic {
+            rendered: Some(rendered),
+            ..
+        },
+        ..
+    }) = message
+    {
+        buffers.write_to_stderr(rendered.clone())?;
+    }
+    Ok(())
+}
+
+/// Returns `true` if the message is printed as soon as it arrives, instead of waiting for the build to finish.
+fn is_streamed(message: &CompilerMessage, options: &Options) -> bool {
+    options.stream_messages
+        && is_error(message)
+        && !message.message.spans.is_empty()
+        && is_shown_lint(message, options)
+}
+
+fn is_error(message: &CompilerMessage) -> bool {
+    matches!(
+        message.message.level,
+        DiagnosticLevel::Error | DiagnosticLevel::Ice
+    )
+}
+
+fn is_shown_lint(message: &CompilerMessage, options: &Options) -> bool {
+    message
+        .message
+        .code
+        .as_ref()
+        .map_or(true, |code| !options.is_lint_hidden(&code.code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    const PACKAGE_ID: &str = "a 0.1.0 (path+file:///a)";
+
+    /// Error with primary spans at the lines, or without spans.
+    fn error(target: &str, lines: &[usize]) -> Result<CompilerMessage> {
+        let spans = lines
+            .iter()
+            .map(|line| {
+                json!({
+                    "file_name": "src/lib.rs",
+                    "byte_start": 0,
+                    "byte_end": 1,
+                    "line_start": line,
+                    "line_end": line,
+                    "column_start": 1,
+                    "column_end": 2,
+                    "is_primary": true,
+                    "text": [],
+                    "label": null,
+                    "suggested_replacement": null,
+                    "suggestion_applicability": null,
+                    "expansion": null,
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(serde_json::from_value(json!({
+            "reason": "compiler-message",
+            "package_id": PACKAGE_ID,
+            "target": {
+                "name": target,
+                "kind": ["lib"],
+                "crate_types": ["lib"],
+                "src_path": "/a/src/lib.rs",
+            },
+            "message": {
+                "message": "",
+                "code": null,
+                "level": "error",
+                "spans": spans,
+                "children": [],
+                "rendered": null,
+            },
+        }))?)
+    }
+
+    #[test]
+    fn streaming_with_limit() -> Result<()> {
+        let workspace = Workspace::new(
+            PathBuf::from("/a"),
+            PathBuf::from("/a/target"),
+            [PackageId {
+                repr: PACKAGE_ID.to_owned(),
+            }],
+        );
+        let shown_lines = |stream_messages: bool| -> Result<(Vec<bool>, Vec<usize>, Summary)> {
+            let mut options = Options::default();
+            options.stream_messages = stream_messages;
+            options.limit_messages = 1;
+            let mut messages = Messages::default();
+            let streamed = [8, 5, 2]
+                .into_iter()
+                .map(|line| Ok(messages.add(error("a", &[line])?, &options)))
+                .collect::<Result<Vec<_>>>()?;
+            let streamed_errors = mem::take(&mut messages.streamed_errors);
+            let transformed =
+                TransformedMessages::transform(messages, &streamed_errors, &options, &workspace)?;
+            let lines = transformed
+                .messages_in_consistent_order
+                .iter()
+                .filter_map(|message| match message {
+                    Message::CompilerMessage(i) => Some(i.message.spans[0].line_start),
+                    _ => None,
+                })
+                .collect();
+            Ok((streamed, lines, transformed.summary))
+        };
+
+        let (streamed, lines, summary) = shown_lines(true)?;
+        assert_eq!(streamed, vec![true, false, false]);
+        assert_eq!(lines, vec![8]);
+        assert_eq!((summary.shown_errors, summary.truncated_errors), (1, 2));
+
+        let (streamed, lines, summary) = shown_lines(false)?;
+        assert_eq!(streamed, vec![false, false, false]);
+        assert_eq!(lines, vec![2]);
+        assert_eq!((summary.shown_errors, summary.truncated_errors), (1, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn hidden_warnings() -> Result<()> {
+        let workspace = Workspace::new(
+            PathBuf::from("/a"),
+            PathBuf::from("/a/target"),
+            [PackageId {
+                repr: PACKAGE_ID.to_owned(),
+            }],
+        );
+        let mut options = Options::default();
+        options.show_members_warnings = false;
+        let warning = |package_id: &str| -> Result<CompilerMessage> {
+            let mut result = error("a", &[1])?;
+            result.package_id.repr = package_id.to_owned();
+            result.message.level = DiagnosticLevel::Warning;
+            Ok(result)
+        };
+
+        let mut messages = Messages::default();
+        messages.add(warning(PACKAGE_ID)?, &options);
+        messages.add(warning("b 0.1.0 (path+file:///b)")?, &options);
+        messages.add(warning("c 0.1.0 (registry+https://example.com)")?, &options);
+        let transformed = TransformedMessages::transform(
+            messages,
+            &StreamedErrors::default(),
+            &options,
+            &workspace,
+        )?;
+        assert_eq!(
+            (
+                transformed.summary.hidden_members_warnings,
+                transformed.summary.hidden_dependencies_warnings,
+            ),
+            (1, 2)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn failed_crates_finished() -> Result<()> {
+        let mut options = Options::default();
+        options.stop_on_failed_crates = true;
+        let message = |target: &str, spans: usize| -> Result<Message> {
+            Ok(Message::CompilerMessage(error(target, &vec![1; spans])?))
+        };
+
+        let mut abort_triggers = AbortTriggers::default();
+        abort_triggers.update(&message("a", 1)?);
+        abort_triggers.update(&message("b", 1)?);
+        abort_triggers.update(&message("a", 0)?);
+        assert!(!abort_triggers.fire(&options, 2));
+        abort_triggers.update(&message("b", 0)?);
+        assert!(abort_triggers.fire(&options, 2));
+        assert!(!abort_triggers.fire(&options, 2));
+        Ok(())
     }
 }
@@ -1,9 +1,25 @@
-use cargo_metadata::diagnostic::{Diagnostic, DiagnosticLevel, DiagnosticSpan};
+use anyhow::{bail, format_err, Error, Result};
+use cargo_metadata::{
+    diagnostic::{Applicability, Diagnostic, DiagnosticLevel, DiagnosticSpan},
+    Message,
+};
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
+/// Version of `EditorData` format, which is independent of crate version.
+/// Minor version is increased when fields are added, major version is increased on breaking changes.
+pub const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion { major: 1, minor: 2 };
+
+/// Affected locations passed to stdin of external app.
 #[derive(Deserialize, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct EditorData {
+    /// `<major>.<minor>`, data of unknown major version should be rejected.
     protocol_version: String,
     workspace_root: PathBuf,
 
@@ -12,18 +28,131 @@ pub struct EditorData {
 }
 
 #[derive(Deserialize, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct Location {
+    /// Absolute path.
     path: PathBuf,
+    /// Starts from 1.
     line: usize,
+    /// Starts from 1, counted in Unicode code points.
     column: usize,
     message: String,
+
+    #[cfg_attr(test, schemars(schema_with = "schema::level"))]
+    level: DiagnosticLevel,
+
+    /// Inclusive, since protocol version 1.1.
+    #[serde(default)]
+    line_end: usize,
+
+    /// Exclusive, since protocol version 1.1.
+    #[serde(default)]
+    column_end: usize,
+
+    /// Like `E0502` or `clippy::needless_return`, since protocol version 1.1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+
+    /// Text attached to the span, since protocol version 1.1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+
+    /// Notes and help messages, since protocol version 1.1.
+    #[serde(default)]
+    children: Vec<Child>,
+
+    /// Message as printed by the compiler, without colors, since protocol version 1.1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rendered: Option<String>,
+
+    /// Compiler's fixes, since protocol version 1.1.
+    #[serde(default)]
+    suggestions: Vec<Suggestion>,
+
+    /// Secondary spans of the same message, since protocol version 1.2.
+    #[serde(default)]
+    related: Vec<RelatedLocation>,
+}
+
+/// Secondary span, like "first borrow occurs here".
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct RelatedLocation {
+    /// Absolute path.
+    path: PathBuf,
+    line: usize,
+    column: usize,
+    line_end: usize,
+    column_end: usize,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct Child {
+    message: String,
+
+    #[cfg_attr(test, schemars(schema_with = "schema::level"))]
     level: DiagnosticLevel,
 }
 
+/// Replacement of the given range.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct Suggestion {
+    /// Absolute path.
+    path: PathBuf,
+    line: usize,
+    column: usize,
+    line_end: usize,
+    column_end: usize,
+    replacement: String,
+
+    #[cfg_attr(test, schemars(schema_with = "schema::applicability"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    applicability: Option<Applicability>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct ProtocolVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+/// Numbers of messages on each filtering stage.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Summary {
+    pub total_errors: usize,
+    pub total_warnings: usize,
+    pub hidden_lints: usize,
+    pub hidden_members_warnings: usize,
+    pub hidden_dependencies_warnings: usize,
+    pub duplicates: usize,
+    pub unique_errors: usize,
+    pub unique_warnings: usize,
+    pub suppressed_warnings: usize,
+    pub truncated_errors: usize,
+    pub truncated_warnings: usize,
+    pub shown_errors: usize,
+    pub shown_warnings: usize,
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub(crate) workspace_root: PathBuf,
+    pub(crate) exit_code: i32,
+    pub(crate) killed_by_time_limit: bool,
+    pub(crate) duration_seconds: f64,
+    pub(crate) summary: Summary,
+    pub(crate) messages: Vec<Message>,
+}
+
 impl EditorData {
     pub fn new(workspace_root: &Path, locations_in_consistent_order: Vec<Location>) -> Self {
         let workspace_root = workspace_root.to_path_buf();
-        let protocol_version = std::env!("CARGO_PKG_VERSION").to_string();
+        let protocol_version = PROTOCOL_VERSION.to_string();
         Self {
             protocol_version,
             workspace_root,
@@ -31,28 +160,656 @@ impl EditorData {
         }
     }
 
+    pub fn protocol_version(&self) -> &str {
+        &self.protocol_version
+    }
+
+    /// Marks data as the current protocol version, fields unknown to it are dropped on deserialization.
+    pub fn downgrade(self) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION.to_string(),
+            ..self
+        }
+    }
+
+    pub fn workspace_root(&self) -> &Path {
+        &self.workspace_root
+    }
+
+    pub fn locations(&self) -> &[Location] {
+        &self.locations
+    }
+
     pub fn escaped_workspace_root(&self) -> String {
-        const ESCAPE_CHAR: &str = "%";
-        self.workspace_root
-            .to_string_lossy()
-            .replace(['/', '\\', ':'], ESCAPE_CHAR)
+        escape_path(&self.workspace_root)
     }
 }
 
 impl Location {
     pub fn new(span: DiagnosticSpan, diagnostic: &Diagnostic, workspace_root: &Path) -> Self {
-        let path = PathBuf::from(span.file_name);
-        let path = if path.is_relative() {
-            workspace_root.join(&path)
-        } else {
-            path
-        };
+        let children = diagnostic
+            .children
+            .iter()
+            .map(|child| Child {
+                message: child.message.clone(),
+                level: child.level,
+            })
+            .collect();
+        let primary_spans = project_primary_spans(diagnostic).collect::<Vec<_>>();
+        let suggestions = diagnostic
+            .spans
+            .iter()
+            .chain(diagnostic.children.iter().flat_map(|child| &child.spans))
+            .filter(|suggestion_span| nearest_span(suggestion_span, &primary_spans) == Some(&span))
+            .filter_map(|span| {
+                span.suggested_replacement
+                    .as_ref()
+                    .map(|replacement| Suggestion {
+                        path: absolute_path(&span.file_name, workspace_root),
+                        line: span.line_start,
+                        column: span.column_start,
+                        line_end: span.line_end,
+                        column_end: span.column_end,
+                        replacement: replacement.clone(),
+                        applicability: span.suggestion_applicability.clone(),
+                    })
+            })
+            .collect();
+        let related = project_secondary_spans(diagnostic)
+            .filter(|related_span| related_span != &span)
+            .map(|related_span| RelatedLocation {
+                path: absolute_path(&related_span.file_name, workspace_root),
+                line: related_span.line_start,
+                column: related_span.column_start,
+                line_end: related_span.line_end,
+                column_end: related_span.column_end,
+                label: related_span.label,
+            })
+            .collect();
         Self {
-            path,
+            path: absolute_path(&span.file_name, workspace_root),
             line: span.line_start,
             column: span.column_start,
             message: diagnostic.message.clone(),
             level: diagnostic.level,
+            line_end: span.line_end,
+            column_end: span.column_end,
+            code: diagnostic.code.as_ref().map(|code| code.code.clone()),
+            label: span.label,
+            children,
+            rendered: diagnostic.rendered.as_deref().map(strip_ansi_escape_codes),
+            suggestions,
+            related,
         }
     }
+
+    /// Panic site of a failed test, labeled with the test name.
+    pub fn from_panic(
+        file_name: &str,
+        line: usize,
+        column: usize,
+        message: String,
+        test_name: &str,
+        workspace_root: &Path,
+    ) -> Self {
+        Self {
+            path: absolute_path(file_name, workspace_root),
+            line,
+            column,
+            message,
+            level: DiagnosticLevel::Error,
+            line_end: line,
+            column_end: column,
+            code: None,
+            label: Some(test_name.to_owned()),
+            children: Vec::new(),
+            rendered: None,
+            suggestions: Vec::new(),
+            related: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn level(&self) -> DiagnosticLevel {
+        self.level
+    }
+
+    pub fn line_end(&self) -> usize {
+        self.line_end
+    }
+
+    pub fn column_end(&self) -> usize {
+        self.column_end
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn related(&self) -> &[RelatedLocation] {
+        &self.related
+    }
+}
+
+impl RelatedLocation {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn line_end(&self) -> usize {
+        self.line_end
+    }
+
+    pub fn column_end(&self) -> usize {
+        self.column_end
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+/// Suggestion belongs to the primary span which is the nearest one in the same file,
+/// so suggestions aren't repeated for each primary span of the message.
+pub(crate) fn nearest_span<'a>(
+    span: &DiagnosticSpan,
+    spans: &'a [DiagnosticSpan],
+) -> Option<&'a DiagnosticSpan> {
+    spans.iter().min_by_key(|other| {
+        (
+            other.file_name != span.file_name,
+            other.line_start.abs_diff(span.line_start),
+        )
+    })
+}
+
+fn absolute_path(file_name: &str, workspace_root: &Path) -> PathBuf {
+    let path = PathBuf::from(file_name);
+    if path.is_relative() {
+        workspace_root.join(&path)
+    } else {
+        path
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = Error;
+
+    /// Crate versions (like `0.0.11`), which were used before, are treated as `1.0`.
+    /// Patch version is ignored otherwise.
+    fn from_str(version: &str) -> Result<Self> {
+        let parts = version
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<Vec<u32>, _>>()
+            .map_err(|_| format_err!("invalid protocol version {version:?}"))?;
+        match parts.as_slice() {
+            [0, _, _] => Ok(Self { major: 1, minor: 0 }),
+            [major, minor] | [major, minor, _] => Ok(Self {
+                major: *major,
+                minor: *minor,
+            }),
+            _ => bail!("invalid protocol version {version:?}"),
+        }
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl Report {
+    pub fn new(
+        workspace_root: &Path,
+        exit_code: i32,
+        killed_by_time_limit: bool,
+        duration: Duration,
+        summary: Summary,
+        messages: Vec<Message>,
+    ) -> Self {
+        Self {
+            workspace_root: workspace_root.to_path_buf(),
+            exit_code,
+            killed_by_time_limit,
+            duration_seconds: duration.as_secs_f64(),
+            summary,
+            messages,
+        }
+    }
+}
+
+impl Summary {
+    pub fn is_empty(&self) -> bool {
+        self.truncated_errors == 0
+            && self.truncated_warnings == 0
+            && self.suppressed_warnings == 0
+            && self.hidden_members_warnings == 0
+            && self.hidden_dependencies_warnings == 0
+            && self.hidden_lints == 0
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.truncated_errors > 0 {
+            parts.push(format!(
+                "showing {} of {}",
+                self.shown_errors,
+                plural(self.unique_errors, "error")
+            ));
+        }
+        if self.truncated_warnings > 0 {
+            parts.push(format!(
+                "showing {} of {}",
+                self.shown_warnings,
+                plural(self.unique_warnings - self.suppressed_warnings, "warning")
+            ));
+        }
+        if self.suppressed_warnings > 0 {
+            parts.push(format!(
+                "{} hidden until errors are fixed",
+                plural(self.suppressed_warnings, "warning")
+            ));
+        }
+        if self.hidden_members_warnings > 0 {
+            parts.push(format!(
+                "{} of workspace members hidden",
+                plural(self.hidden_members_warnings, "warning")
+            ));
+        }
+        if self.hidden_dependencies_warnings > 0 {
+            parts.push(format!(
+                "{} of dependencies hidden",
+                plural(self.hidden_dependencies_warnings, "warning")
+            ));
+        }
+        if self.hidden_lints > 0 {
+            parts.push(format!(
+                "{} hidden by lint filter",
+                plural(self.hidden_lints, "message")
+            ));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+fn plural(number: usize, noun: &str) -> String {
+    if number == 1 {
+        format!("{number} {noun}")
+    } else {
+        format!("{number} {noun}s")
+    }
+}
+
+/// Primary spans located in project files, with macro expansions walked back to call sites.
+pub(crate) fn project_primary_spans(
+    diagnostic: &Diagnostic,
+) -> impl Iterator<Item = DiagnosticSpan> + '_ {
+    project_spans(diagnostic, true)
+}
+
+/// Secondary spans (like "first borrow occurs here") located in project files.
+/// Labels survive walking macro expansions back to call sites.
+pub(crate) fn project_secondary_spans(
+    diagnostic: &Diagnostic,
+) -> impl Iterator<Item = DiagnosticSpan> + '_ {
+    project_spans(diagnostic, false)
+}
+
+fn project_spans(
+    diagnostic: &Diagnostic,
+    is_primary: bool,
+) -> impl Iterator<Item = DiagnosticSpan> + '_ {
+    diagnostic
+        .spans
+        .iter()
+        .filter(move |span| span.is_primary == is_primary)
+        .map(move |span| {
+            let mut project_span = find_leaf_project_expansion(span.clone());
+            if !is_primary && project_span.label.is_none() {
+                project_span.label = span.label.clone();
+            }
+            project_span
+        })
+        .filter(|span| Path::new(&span.file_name).is_relative())
+}
+
+/// Spans with suggested replacements, walked back from macro expansions to call sites,
+/// located in project files.
+pub(crate) fn project_suggested_spans(
+    spans: &[DiagnosticSpan],
+) -> impl Iterator<Item = DiagnosticSpan> + '_ {
+    spans
+        .iter()
+        .filter(|span| span.suggested_replacement.is_some())
+        .map(|span| {
+            let mut project_span = find_leaf_project_expansion(span.clone());
+            project_span
+                .suggested_replacement
+                .clone_from(&span.suggested_replacement);
+            project_span
+                .suggestion_applicability
+                .clone_from(&span.suggestion_applicability);
+            project_span
+        })
+        .filter(|span| Path::new(&span.file_name).is_relative())
+}
+
+pub(crate) fn find_leaf_project_expansion(mut span: DiagnosticSpan) -> DiagnosticSpan {
+    let mut project_span = span.clone();
+    while let Some(expansion) = span.expansion {
+        span = expansion.span;
+        project_span = span.clone();
+    }
+    project_span
+}
+
+/// Replaces path separators and drive colons, so the path could be used as a file name.
+pub(crate) fn escape_path(path: &Path) -> String {
+    const ESCAPE_CHAR: &str = "%";
+    path.to_string_lossy()
+        .replace(['/', '\\', ':'], ESCAPE_CHAR)
+}
+
+pub(crate) fn strip_ansi_escape_codes(text: &str) -> String {
+    const ESCAPE_CHAR: char = '\x1b';
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ESCAPE_CHAR {
+            if chars.next_if_eq(&'[').is_some() {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod schema {
+    use schemars::{
+        gen::SchemaGenerator,
+        schema::{InstanceType, Schema, SchemaObject},
+    };
+
+    const LEVELS: &[&str] = &[
+        "error: internal compiler error",
+        "error",
+        "warning",
+        "failure-note",
+        "note",
+        "help",
+    ];
+
+    const APPLICABILITIES: &[&str] = &[
+        "MachineApplicable",
+        "HasPlaceholders",
+        "MaybeIncorrect",
+        "Unspecified",
+    ];
+
+    pub fn level(_: &mut SchemaGenerator) -> Schema {
+        string_enum(LEVELS)
+    }
+
+    pub fn applicability(_: &mut SchemaGenerator) -> Schema {
+        string_enum(APPLICABILITIES)
+    }
+
+    fn string_enum(values: &[&str]) -> Schema {
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(values.iter().map(|i| (*i).into()).collect()),
+            ..SchemaObject::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::{env, fs};
+
+    const SCHEMA_PATH: &str = "editor_data.schema.json";
+
+    /// Run with `UPDATE_SCHEMA=1` to regenerate the schema file after changing the types.
+    #[test]
+    fn editor_data_schema() -> Result<()> {
+        let schema = serde_json::to_string_pretty(&schemars::schema_for!(EditorData))? + "\n";
+        if env::var_os("UPDATE_SCHEMA").is_some() {
+            fs::write(SCHEMA_PATH, &schema)?;
+        }
+        assert_eq!(fs::read_to_string(SCHEMA_PATH)?, schema);
+        Ok(())
+    }
+
+    #[test]
+    fn location_details() -> Result<()> {
+        let workspace_root = Path::new("/workspace");
+        let span = json!({
+            "file_name": "src/main.rs",
+            "byte_start": 24,
+            "byte_end": 30,
+            "line_start": 2,
+            "line_end": 2,
+            "column_start": 9,
+            "column_end": 15,
+            "is_primary": true,
+            "text": [],
+            "label": null,
+            "suggested_replacement": null,
+            "suggestion_applicability": null,
+            "expansion": null,
+        });
+        let mut suggested_span = span.clone();
+        suggested_span["suggested_replacement"] = json!("_unused");
+        suggested_span["suggestion_applicability"] = json!("MachineApplicable");
+        let warning: Diagnostic = serde_json::from_value(json!({
+            "message": "unused variable: `unused`",
+            "code": { "code": "unused_variables", "explanation": null },
+            "level": "warning",
+            "spans": [span],
+            "children": [{
+                "message": "if this is intentional, prefix it with an underscore",
+                "code": null,
+                "level": "help",
+                "spans": [suggested_span],
+                "children": [],
+                "rendered": null,
+            }],
+            "rendered": "warning: unused variable: `unused`\n",
+        }))?;
+        let span = warning.spans[0].clone();
+        let location = Location::new(span, &warning, workspace_root);
+
+        let value = serde_json::to_value(location)?;
+        assert_eq!(value["path"], "/workspace/src/main.rs");
+        assert_eq!(
+            (
+                &value["line"],
+                &value["column"],
+                &value["line_end"],
+                &value["column_end"]
+            ),
+            (&2.into(), &9.into(), &2.into(), &15.into())
+        );
+        assert_eq!(value["code"], "unused_variables");
+        assert_eq!(
+            value["suggestions"],
+            serde_json::json!([{
+                "path": "/workspace/src/main.rs",
+                "line": 2,
+                "column": 9,
+                "line_end": 2,
+                "column_end": 15,
+                "replacement": "_unused",
+                "applicability": "MachineApplicable",
+            }])
+        );
+        assert!(value["children"]
+            .as_array()
+            .map_or(false, |children| children
+                .iter()
+                .any(|i| i["level"] == "help")));
+        assert!(value["rendered"]
+            .as_str()
+            .map_or(false, |i| i.starts_with("warning: unused variable")));
+        Ok(())
+    }
+
+    #[test]
+    fn suggestions_of_several_primary_spans() -> Result<()> {
+        let span = |line: usize, is_primary: bool, replacement: Option<&str>| {
+            json!({
+                "file_name": "src/main.rs",
+                "byte_start": 0,
+                "byte_end": 1,
+                "line_start": line,
+                "line_end": line,
+                "column_start": 5,
+                "column_end": 6,
+                "is_primary": is_primary,
+                "text": [],
+                "label": null,
+                "suggested_replacement": replacement,
+                "suggestion_applicability": replacement.map(|_| "MachineApplicable"),
+                "expansion": null,
+            })
+        };
+        let diagnostic: Diagnostic = serde_json::from_value(json!({
+            "message": "unused variables",
+            "code": null,
+            "level": "warning",
+            "spans": [span(2, true, None), span(10, true, None)],
+            "children": [{
+                "message": "prefix them with an underscore",
+                "code": null,
+                "level": "help",
+                "spans": [span(2, true, Some("_a")), span(10, true, Some("_b"))],
+                "children": [],
+                "rendered": null,
+            }],
+            "rendered": null,
+        }))?;
+
+        let replacements = diagnostic
+            .spans
+            .iter()
+            .map(|span| {
+                Location::new(span.clone(), &diagnostic, Path::new("/workspace"))
+                    .suggestions
+                    .into_iter()
+                    .map(|suggestion| suggestion.replacement)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(replacements, vec![vec!["_a"], vec!["_b"]]);
+        Ok(())
+    }
+
+    #[test]
+    fn protocol_versions() -> Result<()> {
+        assert_eq!(
+            PROTOCOL_VERSION.to_string().parse::<ProtocolVersion>()?,
+            PROTOCOL_VERSION
+        );
+        assert_eq!(
+            "0.0.11".parse::<ProtocolVersion>()?,
+            ProtocolVersion { major: 1, minor: 0 }
+        );
+        assert_eq!(
+            "2.3".parse::<ProtocolVersion>()?,
+            ProtocolVersion { major: 2, minor: 3 }
+        );
+        assert_eq!(
+            "2.0.0".parse::<ProtocolVersion>()?,
+            ProtocolVersion { major: 2, minor: 0 }
+        );
+        assert!("2".parse::<ProtocolVersion>().is_err());
+        assert!("v2.0".parse::<ProtocolVersion>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn summary_footer() {
+        assert!(Summary::default().is_empty());
+        assert_eq!(Summary::default().to_string(), "");
+
+        let summary = Summary {
+            total_errors: 20,
+            total_warnings: 45,
+            duplicates: 3,
+            unique_errors: 17,
+            unique_warnings: 42,
+            suppressed_warnings: 42,
+            shown_errors: 3,
+            truncated_errors: 14,
+            ..Summary::default()
+        };
+        assert!(!summary.is_empty());
+        assert_eq!(
+            summary.to_string(),
+            "showing 3 of 17 errors; 42 warnings hidden until errors are fixed"
+        );
+
+        let summary = Summary {
+            total_warnings: 4,
+            unique_warnings: 2,
+            shown_warnings: 1,
+            truncated_warnings: 1,
+            hidden_dependencies_warnings: 1,
+            hidden_lints: 1,
+            ..Summary::default()
+        };
+        assert_eq!(
+            summary.to_string(),
+            "showing 1 of 2 warnings; 1 warning of dependencies hidden; 1 message hidden by lint \
+             filter"
+        );
+
+        let summary = Summary {
+            total_warnings: 3,
+            hidden_members_warnings: 2,
+            hidden_dependencies_warnings: 1,
+            ..Summary::default()
+        };
+        assert_eq!(
+            summary.to_string(),
+            "2 warnings of workspace members hidden; 1 warning of dependencies hidden"
+        );
+    }
 }
@@ -0,0 +1,229 @@
+pub mod neovim;
+
+use crate::models::{EditorData, ProtocolVersion, PROTOCOL_VERSION};
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::{
+    env,
+    io::{self, Read, Write},
+    process::{Command, ExitStatus, Output},
+};
+
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+
+const PROTOCOL_VERSION_FIELD: &str = "protocol_version";
+const EXPECTED_EXPR_RESULT: &str = "0";
+const VIM_IS_NOT_RUNNING_ERRORS: &[&str] = &["E247:", "Send expression failed."];
+
+#[cfg(unix)]
+const FALLBACK_RUNTIME_DIR: &str = "/tmp";
+
+/// Per-user and per-workspace address of a Unix socket or a Windows named pipe.
+pub fn listen_address(prefix: &str, escaped_workspace_root: &str) -> Result<String> {
+    let result = {
+        #[cfg(unix)]
+        {
+            let dir = socket_dirs(prefix, runtime_dir().as_deref(), &user()).remove(0);
+            dir.join(escaped_workspace_root)
+                .to_string_lossy()
+                .into_owned()
+        }
+
+        #[cfg(windows)]
+        {
+            let user = env::var("USERNAME")?;
+            format!(r"\\.\pipe\{prefix}{user}-{escaped_workspace_root}")
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        {
+            compile_error!("this platform is unsupported")
+        }
+    };
+
+    Ok(result)
+}
+
+/// Per-user directories of Unix sockets: the one to listen in goes first,
+/// it's in `$XDG_RUNTIME_DIR` if it's set, while `/tmp` is the fallback.
+#[cfg(unix)]
+pub fn socket_dirs(prefix: &str, runtime_dir: Option<&Path>, user: &str) -> Vec<PathBuf> {
+    let dir_name = format!("{prefix}{user}");
+    let fallback = Path::new(FALLBACK_RUNTIME_DIR).join(&dir_name);
+    match runtime_dir {
+        Some(runtime_dir) => {
+            let dir = runtime_dir.join(&dir_name);
+            if dir == fallback {
+                vec![dir]
+            } else {
+                vec![dir, fallback]
+            }
+        },
+        None => vec![fallback],
+    }
+}
+
+#[cfg(unix)]
+pub fn runtime_dir() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR")
+        .filter(|i| !i.is_empty())
+        .map(PathBuf::from)
+}
+
+/// User name, or uid if it's unknown.
+#[cfg(unix)]
+pub fn user() -> String {
+    match env::var("USER") {
+        Ok(user) if !user.is_empty() => user,
+        _ => unsafe { libc::getuid() }.to_string(),
+    }
+}
+
+/// Reads `EditorData` passed to stdin, along with its raw JSON.
+///
+/// Data of newer minor protocol version is downgraded to the current one.
+/// Data of unknown major protocol version is rejected.
+pub fn read_editor_data<R: Read>(mut input: R) -> Result<(String, EditorData)> {
+    let mut raw_editor_data = String::new();
+    input.read_to_string(&mut raw_editor_data)?;
+
+    let value: Value = serde_json::from_str(&raw_editor_data)?;
+    let version = value
+        .get(PROTOCOL_VERSION_FIELD)
+        .and_then(Value::as_str)
+        .context("protocol version is missing")?
+        .parse::<ProtocolVersion>()?;
+    if version.major != PROTOCOL_VERSION.major {
+        bail!(
+            "unsupported protocol version {version} (expected {}.x), \
+             cargo-limit and the editor opener should be updated to the same version",
+            PROTOCOL_VERSION.major
+        );
+    }
+
+    let editor_data: EditorData = serde_json::from_value(value)?;
+    if version > PROTOCOL_VERSION {
+        let editor_data = editor_data.downgrade();
+        Ok((serde_json::to_string(&editor_data)?, editor_data))
+    } else {
+        Ok((raw_editor_data, editor_data))
+    }
+}
+
+/// Runs Vim with `--remote-expr`, keeping quiet if there's no editor to control.
+pub fn run_remote_expr(command: Command) -> Result<ExitStatus> {
+    run_editor_client(command, EXPECTED_EXPR_RESULT, VIM_IS_NOT_RUNNING_ERRORS)
+}
+
+/// Runs editor client, forwarding its output unless it's expected one
+/// or consists of errors saying that editor isn't running.
+pub fn run_editor_client(
+    mut command: Command,
+    expected_output: &str,
+    editor_is_not_running_errors: &[&str],
+) -> Result<ExitStatus> {
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = command.output()?;
+
+    if String::from_utf8_lossy(&stdout).trim() != expected_output {
+        let mut stdout_writer = io::stdout();
+        stdout_writer.write_all(&stdout)?;
+        stdout_writer.flush()?;
+    }
+
+    if !editor_is_not_running(
+        &String::from_utf8_lossy(&stderr),
+        editor_is_not_running_errors,
+    ) {
+        let mut stderr_writer = io::stderr();
+        stderr_writer.write_all(&stderr)?;
+        stderr_writer.flush()?;
+    }
+
+    Ok(status)
+}
+
+/// Returns `true` if every non-empty line of client stderr is one of the errors
+/// saying that editor isn't running.
+pub fn editor_is_not_running(stderr: &str, editor_is_not_running_errors: &[&str]) -> bool {
+    stderr.lines().filter(|line| !line.is_empty()).all(|line| {
+        editor_is_not_running_errors
+            .iter()
+            .any(|error| line.starts_with(error))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn protocol_negotiation() -> Result<()> {
+        let location = json!({
+            "path": "/workspace/src/main.rs",
+            "line": 5,
+            "column": 5,
+            "message": "cannot borrow `v` as mutable because it is also borrowed as immutable",
+            "level": "error",
+        });
+
+        let raw = json!({
+            "protocol_version": "0.0.11",
+            "workspace_root": "/workspace",
+            "files": [location],
+        })
+        .to_string();
+        let (read_raw, editor_data) = read_editor_data(raw.as_bytes())?;
+        assert_eq!(read_raw, raw);
+        assert_eq!(editor_data.locations().len(), 1);
+
+        let mut newer_location = location.clone();
+        newer_location["unknown"] = json!(true);
+        let raw = json!({
+            "protocol_version": "1.99",
+            "workspace_root": "/workspace",
+            "files": [newer_location],
+            "unknown": {},
+        })
+        .to_string();
+        let (read_raw, _) = read_editor_data(raw.as_bytes())?;
+        let mut downgraded_location = location.clone();
+        for (field, default) in [
+            ("line_end", json!(0)),
+            ("column_end", json!(0)),
+            ("children", json!([])),
+            ("suggestions", json!([])),
+            ("related", json!([])),
+        ] {
+            downgraded_location[field] = default;
+        }
+        assert_eq!(
+            serde_json::from_str::<Value>(&read_raw)?,
+            json!({
+                "protocol_version": PROTOCOL_VERSION.to_string(),
+                "workspace_root": "/workspace",
+                "files": [downgraded_location],
+            })
+        );
+
+        let raw = json!({ "protocol_version": "2.0", "files": "changed" }).to_string();
+        let error = read_editor_data(raw.as_bytes())
+            .err()
+            .context("unsupported version is accepted")?;
+        assert!(error
+            .to_string()
+            .starts_with("unsupported protocol version 2.0 (expected 1.x)"));
+
+        let raw = json!({ "protocol_version": "2.0.0", "files": "changed" }).to_string();
+        assert!(read_editor_data(raw.as_bytes()).is_err());
+
+        assert!(read_editor_data(b"{}".as_slice()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,455 @@
+use crate::models::escape_path;
+use anyhow::{bail, Context, Result};
+use rmpv::Value;
+use serde::Serialize;
+use std::{
+    env,
+    error::Error,
+    fmt,
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use {
+    super::{runtime_dir, socket_dirs, user},
+    itertools::Itertools,
+    std::{fs, os::unix::net::UnixStream, path::PathBuf, time::SystemTime},
+};
+
+#[cfg(windows)]
+use std::{fs::File, sync::mpsc, thread};
+
+pub const MODES_VAR: &str = "CARGO_NVIM_MODES";
+pub const INSTANCES_VAR: &str = "CARGO_NVIM_INSTANCES";
+
+const REQUEST: u64 = 0;
+const RESPONSE: u64 = 1;
+const EXEC_LUA_METHOD: &str = "nvim_exec_lua";
+const CALL_FUNCTION_METHOD: &str = "nvim_call_function";
+
+/// Neovim which is blocked (like by a prompt) shouldn't block the build.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Address of the instance whose terminal runs this process.
+const PARENT_INSTANCE_VAR: &str = "NVIM";
+
+#[cfg(unix)]
+type Stream = UnixStream;
+
+#[cfg(windows)]
+type Stream = TimedPipe;
+
+/// Named pipe has no read and write timeouts, so each operation runs on a thread with a deadline.
+/// Connection shouldn't be used after the timeout, since the operation may be still pending.
+#[cfg(windows)]
+pub struct TimedPipe(File);
+
+/// Connection to a running Neovim instance.
+pub struct Instance {
+    pub address: String,
+    pub pid: u64,
+    pub client: Client<Stream>,
+}
+
+/// Minimal [msgpack-RPC](https://github.com/msgpack-rpc/msgpack-rpc/blob/master/spec.md)
+/// client of Neovim API.
+pub struct Client<S: Read + Write> {
+    reader: BufReader<S>,
+    writer: BufWriter<S>,
+    next_id: u32,
+}
+
+/// Error returned by Neovim, like Lua exception.
+#[derive(Debug)]
+pub struct NeovimError {
+    message: String,
+}
+
+impl Client<Stream> {
+    pub fn connect(address: &str) -> io::Result<Self> {
+        #[cfg(unix)]
+        let stream = {
+            let stream = UnixStream::connect(address)?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            stream
+        };
+
+        #[cfg(windows)]
+        let stream = TimedPipe(
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(address)?,
+        );
+
+        Ok(Self::new(stream.try_clone()?, stream))
+    }
+}
+
+/// Running Neovim instances for the workspace, the most relevant go first:
+/// the one whose terminal runs this process, then the most recently started ones.
+/// Instances of enclosing workspaces are looked for if there are none for the workspace itself.
+pub fn discover(prefix: &str, workspace_root: &Path) -> Result<Vec<Instance>> {
+    let parent_address = env::var(PARENT_INSTANCE_VAR).ok().filter(|i| !i.is_empty());
+
+    #[cfg(unix)]
+    let socket_dirs = socket_dirs(prefix, runtime_dir().as_deref(), &user());
+    #[cfg(unix)]
+    let server_addresses = |workspace_root: &Path| server_addresses(&socket_dirs, workspace_root);
+
+    #[cfg(windows)]
+    let server_addresses = |workspace_root: &Path| server_addresses(prefix, workspace_root);
+
+    discover_with(parent_address.as_deref(), workspace_root, server_addresses)
+}
+
+fn discover_with(
+    parent_address: Option<&str>,
+    workspace_root: &Path,
+    server_addresses: impl Fn(&Path) -> Result<Vec<String>>,
+) -> Result<Vec<Instance>> {
+    let mut result: Vec<Instance> = Vec::new();
+    if let Some(address) = parent_address {
+        result.extend(Instance::connect_or_log(address, false));
+    }
+
+    for workspace_root in workspace_root.ancestors() {
+        let mut found = false;
+        for address in server_addresses(workspace_root)? {
+            if let Some(instance) = Instance::connect_or_log(&address, true) {
+                found = true;
+                if result.iter().all(|i| i.pid != instance.pid) {
+                    result.push(instance);
+                }
+            }
+        }
+        if found {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Sockets created by each instance in per-workspace directory, the newest first,
+/// or a single socket of older plugin versions.
+#[cfg(unix)]
+fn server_addresses(socket_dirs: &[PathBuf], workspace_root: &Path) -> Result<Vec<String>> {
+    let escaped_workspace_root = escape_path(workspace_root);
+    let mut result = Vec::new();
+    for dir in socket_dirs {
+        let path = dir.join(&escaped_workspace_root);
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => {
+                let sockets = fs::read_dir(&path)?
+                    .map(|entry| {
+                        let path = entry?.path();
+                        let modified = fs::symlink_metadata(&path)?.modified()?;
+                        Ok((modified, path))
+                    })
+                    .collect::<io::Result<Vec<(SystemTime, PathBuf)>>>()?
+                    .into_iter()
+                    .sorted_by(|(left, _), (right, _)| right.cmp(left))
+                    .map(|(_, path)| path);
+                result.extend(sockets);
+            },
+            Ok(_) => result.push(path),
+            Err(_) => (),
+        }
+    }
+    Ok(result
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+#[cfg(windows)]
+fn server_addresses(prefix: &str, workspace_root: &Path) -> Result<Vec<String>> {
+    let escaped_workspace_root = escape_path(workspace_root);
+    Ok(vec![super::listen_address(
+        prefix,
+        &escaped_workspace_root,
+    )?])
+}
+
+impl Instance {
+    /// Instance which can't be connected to for other reasons is skipped, so others still can be.
+    fn connect_or_log(address: &str, remove_if_stale: bool) -> Option<Self> {
+        Self::connect(address, remove_if_stale).unwrap_or_else(|err| {
+            eprintln!("{err:#}");
+            None
+        })
+    }
+
+    /// Returns `None` if nothing listens to the address, optionally removing the stale socket.
+    fn connect(address: &str, remove_if_stale: bool) -> Result<Option<Self>> {
+        match Client::connect(address) {
+            Ok(mut client) => {
+                let pid = client
+                    .call(
+                        CALL_FUNCTION_METHOD,
+                        vec!["getpid".into(), Value::Array(vec![])],
+                    )?
+                    .as_u64()
+                    .context("invalid pid")?;
+                Ok(Some(Self {
+                    address: address.to_owned(),
+                    pid,
+                    client,
+                }))
+            },
+            Err(err) if err.kind() == ErrorKind::ConnectionRefused => {
+                if remove_if_stale {
+                    let _ = std::fs::remove_file(address);
+                }
+                Ok(None)
+            },
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("failed to connect to {address:?}")),
+        }
+    }
+}
+
+impl<S: Read + Write> Client<S> {
+    fn new(reader: S, writer: S) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            writer: BufWriter::new(writer),
+            next_id: 0,
+        }
+    }
+
+    /// Executes Lua chunk, which receives the arguments as `...`.
+    pub fn exec_lua(&mut self, code: &str, args: &[impl Serialize]) -> Result<Value> {
+        let args = args
+            .iter()
+            .map(rmpv::ext::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.call(EXEC_LUA_METHOD, vec![code.into(), Value::Array(args)])
+    }
+
+    pub fn call(&mut self, method: &str, params: Vec<Value>) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        let request = Value::Array(vec![
+            REQUEST.into(),
+            id.into(),
+            method.into(),
+            Value::Array(params),
+        ]);
+        rmpv::encode::write_value(&mut self.writer, &request)?;
+        self.writer.flush()?;
+
+        loop {
+            let message = rmpv::decode::read_value(&mut self.reader)?;
+            match message.as_array().map(Vec::as_slice) {
+                Some([kind, response_id, error, result])
+                    if kind.as_u64() == Some(RESPONSE)
+                        && response_id.as_u64() == Some(id.into()) =>
+                {
+                    return if error.is_nil() {
+                        Ok(result.clone())
+                    } else {
+                        Err(NeovimError::from(error).into())
+                    };
+                },
+                Some(_) => (),
+                None => bail!("unexpected message from nvim: {message}"),
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+impl TimedPipe {
+    fn try_clone(&self) -> io::Result<Self> {
+        self.0.try_clone().map(Self)
+    }
+
+    fn with_deadline<T: Send + 'static>(
+        &self,
+        operation: impl FnOnce(File) -> io::Result<T> + Send + 'static,
+    ) -> io::Result<T> {
+        let file = self.0.try_clone()?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(operation(file));
+        });
+        receiver
+            .recv_timeout(TIMEOUT)
+            .map_err(|_| io::Error::new(ErrorKind::TimedOut, "nvim didn't respond in time"))?
+    }
+}
+
+#[cfg(windows)]
+impl Read for TimedPipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len();
+        let data = self.with_deadline(move |mut file| {
+            let mut data = vec![0; len];
+            let read = file.read(&mut data)?;
+            data.truncate(read);
+            Ok(data)
+        })?;
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+}
+
+#[cfg(windows)]
+impl Write for TimedPipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let data = buf.to_vec();
+        self.with_deadline(move |mut file| file.write(&data))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl From<&Value> for NeovimError {
+    /// Neovim sends `[type, message]` errors.
+    fn from(error: &Value) -> Self {
+        let message = match error.as_array().map(Vec::as_slice) {
+            Some([_, message]) => message.as_str().unwrap_or_default(),
+            _ => "",
+        };
+        let message = if message.is_empty() {
+            error.to_string()
+        } else {
+            message.to_owned()
+        };
+        Self { message }
+    }
+}
+
+impl fmt::Display for NeovimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "nvim: {}", self.message)
+    }
+}
+
+impl Error for NeovimError {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::{
+        os::unix::net::{UnixListener, UnixStream},
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn exec_lua() -> Result<()> {
+        let (client_stream, server_stream) = UnixStream::pair()?;
+        let server = thread::spawn(move || -> Result<Vec<Value>> {
+            let mut reader = BufReader::new(server_stream.try_clone()?);
+            let mut writer = server_stream;
+            let mut requests = Vec::new();
+            for response in [
+                vec![
+                    2.into(),
+                    "nvim_buf_lines_event".into(),
+                    Value::Array(vec![]),
+                ],
+                vec![RESPONSE.into(), 0.into(), Value::Nil, 42.into()],
+                vec![
+                    RESPONSE.into(),
+                    1.into(),
+                    Value::Array(vec![0.into(), "Lua: attempt to call a nil value".into()]),
+                    Value::Nil,
+                ],
+            ] {
+                if response[0].as_u64() == Some(RESPONSE) {
+                    requests.push(rmpv::decode::read_value(&mut reader)?);
+                }
+                rmpv::encode::write_value(&mut writer, &Value::Array(response))?;
+            }
+            Ok(requests)
+        });
+
+        let mut client = Client::new(client_stream.try_clone()?, client_stream);
+        let result = client.exec_lua("return ...", &[serde_json::json!({ "line": 5 })])?;
+        assert_eq!(result, Value::from(42));
+        let error = client.exec_lua("undefined()", &[] as &[()]).unwrap_err();
+        assert_eq!(error.to_string(), "nvim: Lua: attempt to call a nil value");
+
+        let requests = server.join().unwrap()?;
+        assert_eq!(
+            requests[0],
+            Value::Array(vec![
+                REQUEST.into(),
+                0.into(),
+                "nvim_exec_lua".into(),
+                Value::Array(vec![
+                    "return ...".into(),
+                    Value::Array(vec![Value::Map(vec![("line".into(), 5.into())])]),
+                ]),
+            ])
+        );
+        assert_eq!(requests[1].as_array().unwrap()[1], Value::from(1));
+        Ok(())
+    }
+
+    #[test]
+    fn discovery() -> Result<()> {
+        const PREFIX: &str = "test-nvim-cargo-limit-";
+
+        const USER: &str = "user";
+
+        let runtime_dir = tempfile::tempdir()?;
+        let dirs = socket_dirs(PREFIX, Some(runtime_dir.path()), USER);
+        assert_eq!(
+            dirs,
+            vec![
+                runtime_dir.path().join(format!("{PREFIX}{USER}")),
+                PathBuf::from(format!("/tmp/{PREFIX}{USER}")),
+            ]
+        );
+        assert_eq!(
+            socket_dirs(PREFIX, None, USER),
+            vec![PathBuf::from(format!("/tmp/{PREFIX}{USER}"))]
+        );
+        let dirs = &dirs[..1];
+
+        let outer_workspace = dirs[0].join(escape_path(Path::new("/outer")));
+        fs::create_dir_all(&outer_workspace)?;
+        let stale_socket = outer_workspace.join("1");
+        drop(UnixListener::bind(&stale_socket)?);
+        fake_instance(&outer_workspace.join("2"), 2)?;
+        thread::sleep(Duration::from_millis(10));
+        fake_instance(&outer_workspace.join("3"), 3)?;
+
+        let server_addresses = |workspace_root: &Path| server_addresses(dirs, workspace_root);
+        let instances = discover_with(None, Path::new("/outer/inner"), server_addresses)?;
+        let pids = instances.iter().map(|i| i.pid).collect::<Vec<_>>();
+        assert_eq!(pids, vec![3, 2]);
+        assert!(!stale_socket.exists());
+        assert!(discover_with(None, Path::new("/elsewhere"), server_addresses)?.is_empty());
+        Ok(())
+    }
+
+    /// Answers `getpid()` calls.
+    fn fake_instance(address: &Path, pid: u64) -> Result<()> {
+        let listener = UnixListener::bind(address)?;
+        thread::spawn(move || -> Result<()> {
+            let (stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let mut writer = stream;
+            while let Ok(request) = rmpv::decode::read_value(&mut reader) {
+                let id = request[1].clone();
+                let response = vec![RESPONSE.into(), id, Value::Nil, pid.into()];
+                rmpv::encode::write_value(&mut writer, &Value::Array(response))?;
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -1,4 +1,11 @@
-use crate::{cargo_toml::CargoToml, process::CARGO_EXECUTABLE};
+use crate::{
+    cargo_toml::CargoToml,
+    config::Config,
+    lints::LintPatterns,
+    opener::neovim,
+    process::CARGO_EXECUTABLE,
+    report::{CiAnnotations, ReportFormat},
+};
 use anyhow::{format_err, Context, Result};
 use const_format::concatcp;
 use itertools::Either;
@@ -8,6 +15,8 @@ const EXECUTABLE_PREFIX: &str = concatcp!(CARGO_EXECUTABLE, "-l");
 
 const APP_ARGS_DELIMITER: &str = "--";
 
+const WATCH_SUBCOMMAND: &str = "watch";
+
 const MESSAGE_FORMAT: &str = "--message-format=";
 const MESSAGE_FORMAT_JSON: &str = concatcp!(MESSAGE_FORMAT, JSON_FORMAT);
 const MESSAGE_FORMAT_JSON_WITH_COLORS: &str = concatcp!(MESSAGE_FORMAT, JSON_FORMAT_WITH_COLORS);
@@ -33,6 +42,31 @@ const COLOR_ALWAYS: &str = "always";
 const COLOR_NEVER: &str = "never";
 const VALID_COLORS: &[&str] = &[COLOR_AUTO, COLOR_ALWAYS, COLOR_NEVER];
 
+const LIMIT_MESSAGES: &str = "--limit-messages=";
+const TIME_LIMIT: &str = "--time-limit=";
+const INT_GRACE: &str = "--int-grace=";
+const TERM_GRACE: &str = "--term-grace=";
+const ERROR_LIMIT: &str = "--error-limit=";
+const STOP_ON_FAILED_CRATES: &str = "--stop-on-failed-crates";
+const STOP_ON_MSG_LIMIT: &str = "--stop-on-msg-limit";
+const STREAM: &str = "--stream";
+const STREAM_REPRINT: &str = "--stream-reprint";
+const ASC: &str = "--asc";
+const FORCE_WARN: &str = "--force-warn";
+const MEMBERS_WARN: &str = "--members-warn";
+const DEPS_WARN: &str = "--deps-warn";
+const REMOTE_DEPS_WARN: &str = "--remote-deps-warn";
+const SUMMARY: &str = "--summary";
+const PROGRESS: &str = "--progress";
+const EDITOR: &str = "--editor=";
+const REPORT: &str = "--report=";
+const REPORT_FORMAT: &str = "--report-format=";
+const CI_ANNOTATIONS: &str = "--ci-annotations=";
+const PUBLISH_DIAGNOSTICS: &str = "--publish-diagnostics";
+const JUMP_TO_RELATED: &str = "--jump-to-related";
+const HIDE_LINTS: &str = "--hide-lints=";
+const SHOW_LINTS: &str = "--show-lints=";
+
 #[derive(Debug, PartialEq)]
 pub struct Options {
     cargo_args: Vec<String>,
@@ -41,13 +75,35 @@ pub struct Options {
 
     pub limit_messages: usize,
     pub time_limit_after_error: Option<Duration>,
+    pub interrupt_grace_period: Duration,
+    pub terminate_grace_period: Duration,
+    pub error_limit: usize,
+    pub stop_on_failed_crates: bool,
+    pub stop_on_messages_limit: bool,
+    pub stream_messages: bool,
+    pub reprint_streamed_messages: bool,
     pub ascending_messages_order: bool,
     pub show_warnings_if_errors_exist: bool,
+    pub show_members_warnings: bool,
     pub show_dependencies_warnings: bool,
+    pub show_remote_dependencies_warnings: bool,
+    pub show_summary: bool,
+    pub show_progress: bool,
     pub open_in_external_app: String,
     pub open_in_external_app_on_warnings: bool,
+    pub jump_to_related_locations: bool,
+    pub publish_diagnostics: bool,
+    pub nvim_modes: String,
+    pub nvim_instances: String,
+    pub report: String,
+    pub report_format: ReportFormat,
+    pub ci_annotations: CiAnnotations,
+    pub hidden_lints: LintPatterns,
+    pub shown_lints: LintPatterns,
     pub help: bool,
     pub version: bool,
+    pub watch: bool,
+    pub parse_test_failures: bool,
     pub json_message_format: bool,
     short_message_format: bool,
 }
@@ -56,6 +112,7 @@ pub struct Options {
 struct ParsedSubcommand {
     subcommand: String,
     open_in_external_app_on_warnings: bool,
+    watch: bool,
     remaining_args: Vec<String>,
 }
 
@@ -67,13 +124,35 @@ impl Default for Options {
             terminal_supports_colors: true,
             limit_messages: 0,
             time_limit_after_error: Some(Duration::from_secs(1)),
+            interrupt_grace_period: Duration::from_secs(1),
+            terminate_grace_period: Duration::from_secs(1),
+            error_limit: 0,
+            stop_on_failed_crates: false,
+            stop_on_messages_limit: false,
+            stream_messages: false,
+            reprint_streamed_messages: false,
             ascending_messages_order: false,
             show_warnings_if_errors_exist: false,
+            show_members_warnings: true,
             show_dependencies_warnings: false,
+            show_remote_dependencies_warnings: false,
+            show_summary: true,
+            show_progress: true,
             open_in_external_app: "_cargo-limit-open-in-nvim".to_owned(),
             open_in_external_app_on_warnings: false,
+            jump_to_related_locations: false,
+            publish_diagnostics: false,
+            nvim_modes: String::new(),
+            nvim_instances: String::new(),
+            report: String::new(),
+            report_format: ReportFormat::default(),
+            ci_annotations: CiAnnotations::default(),
+            hidden_lints: LintPatterns::default(),
+            shown_lints: LintPatterns::default(),
             help: false,
             version: false,
+            watch: false,
+            parse_test_failures: false,
             json_message_format: false,
             short_message_format: false,
         }
@@ -95,16 +174,23 @@ impl Options {
     }
 
     pub fn from_os_env(current_exe: String, workspace_root: &Path) -> Result<Self> {
-        Self::from_vars_and_atty()?.process_args(current_exe, env::args(), workspace_root)
+        Self::from_configs_and_vars_and_atty(workspace_root)?.process_args(
+            current_exe,
+            env::args(),
+            workspace_root,
+        )
     }
 
-    // TODO: rename
-    fn from_vars_and_atty() -> Result<Self> {
+    fn from_configs_and_vars_and_atty(workspace_root: &Path) -> Result<Self> {
         let mut result = Self {
             terminal_supports_colors: io::stderr().is_terminal(),
             ..Self::default()
         };
 
+        for config in Config::parse_all(workspace_root)? {
+            result.apply_config(config)?;
+        }
+
         {
             let mut seconds = result
                 .time_limit_after_error
@@ -112,27 +198,163 @@ impl Options {
                 .map(Duration::as_secs)
                 .unwrap_or(0);
             Self::parse_var("CARGO_TIME_LIMIT", &mut seconds)?;
+            result.set_time_limit_after_error(seconds);
+        }
 
-            let duration = Duration::from_secs(seconds);
-            result.time_limit_after_error = if duration > Duration::from_secs(0) {
-                Some(duration)
-            } else {
-                None
-            };
+        {
+            let mut seconds = result.interrupt_grace_period.as_secs();
+            Self::parse_var("CARGO_INT_GRACE", &mut seconds)?;
+            result.interrupt_grace_period = Duration::from_secs(seconds);
+        }
+
+        {
+            let mut seconds = result.terminate_grace_period.as_secs();
+            Self::parse_var("CARGO_TERM_GRACE", &mut seconds)?;
+            result.terminate_grace_period = Duration::from_secs(seconds);
         }
 
         Self::parse_var("CARGO_MSG_LIMIT", &mut result.limit_messages)?;
+        Self::parse_var("CARGO_ERROR_LIMIT", &mut result.error_limit)?;
+        Self::parse_var(
+            "CARGO_STOP_ON_FAILED_CRATES",
+            &mut result.stop_on_failed_crates,
+        )?;
+        Self::parse_var(
+            "CARGO_STOP_ON_MSG_LIMIT",
+            &mut result.stop_on_messages_limit,
+        )?;
+        Self::parse_var("CARGO_STREAM", &mut result.stream_messages)?;
+        Self::parse_var(
+            "CARGO_STREAM_REPRINT",
+            &mut result.reprint_streamed_messages,
+        )?;
         Self::parse_var("CARGO_ASC", &mut result.ascending_messages_order)?;
         Self::parse_var(
             "CARGO_FORCE_WARN",
             &mut result.show_warnings_if_errors_exist,
         )?;
+        Self::parse_var("CARGO_MEMBERS_WARN", &mut result.show_members_warnings)?;
         Self::parse_var("CARGO_DEPS_WARN", &mut result.show_dependencies_warnings)?;
+        Self::parse_var(
+            "CARGO_REMOTE_DEPS_WARN",
+            &mut result.show_remote_dependencies_warnings,
+        )?;
+        Self::parse_var("CARGO_SUMMARY", &mut result.show_summary)?;
+        Self::parse_var("CARGO_PROGRESS", &mut result.show_progress)?;
         Self::parse_var("CARGO_EDITOR", &mut result.open_in_external_app)?;
+        Self::parse_var(
+            "CARGO_JUMP_TO_RELATED",
+            &mut result.jump_to_related_locations,
+        )?;
+        Self::parse_var("CARGO_PUBLISH_DIAGNOSTICS", &mut result.publish_diagnostics)?;
+        Self::parse_var(neovim::MODES_VAR, &mut result.nvim_modes)?;
+        Self::parse_var(neovim::INSTANCES_VAR, &mut result.nvim_instances)?;
+        Self::parse_var("CARGO_REPORT", &mut result.report)?;
+        Self::parse_var("CARGO_REPORT_FORMAT", &mut result.report_format)?;
+        Self::parse_var("CARGO_CI_ANNOTATIONS", &mut result.ci_annotations)?;
+        Self::parse_var("CARGO_HIDE_LINTS", &mut result.hidden_lints)?;
+        Self::parse_var("CARGO_SHOW_LINTS", &mut result.shown_lints)?;
 
         Ok(result)
     }
 
+    fn apply_config(&mut self, config: Config) -> Result<()> {
+        if let Some(seconds) = config.time_limit {
+            self.set_time_limit_after_error(seconds);
+        }
+        if let Some(seconds) = config.int_grace {
+            self.interrupt_grace_period = Duration::from_secs(seconds);
+        }
+        if let Some(seconds) = config.term_grace {
+            self.terminate_grace_period = Duration::from_secs(seconds);
+        }
+        if let Some(limit_messages) = config.msg_limit {
+            self.limit_messages = limit_messages;
+        }
+        if let Some(error_limit) = config.error_limit {
+            self.error_limit = error_limit;
+        }
+        if let Some(stop_on_failed_crates) = config.stop_on_failed_crates {
+            self.stop_on_failed_crates = stop_on_failed_crates;
+        }
+        if let Some(stop_on_messages_limit) = config.stop_on_msg_limit {
+            self.stop_on_messages_limit = stop_on_messages_limit;
+        }
+        if let Some(stream_messages) = config.stream {
+            self.stream_messages = stream_messages;
+        }
+        if let Some(reprint_streamed_messages) = config.stream_reprint {
+            self.reprint_streamed_messages = reprint_streamed_messages;
+        }
+        if let Some(ascending_messages_order) = config.asc {
+            self.ascending_messages_order = ascending_messages_order;
+        }
+        if let Some(show_warnings_if_errors_exist) = config.force_warn {
+            self.show_warnings_if_errors_exist = show_warnings_if_errors_exist;
+        }
+        if let Some(show_members_warnings) = config.members_warn {
+            self.show_members_warnings = show_members_warnings;
+        }
+        if let Some(show_dependencies_warnings) = config.deps_warn {
+            self.show_dependencies_warnings = show_dependencies_warnings;
+        }
+        if let Some(show_remote_dependencies_warnings) = config.remote_deps_warn {
+            self.show_remote_dependencies_warnings = show_remote_dependencies_warnings;
+        }
+        if let Some(show_summary) = config.summary {
+            self.show_summary = show_summary;
+        }
+        if let Some(show_progress) = config.progress {
+            self.show_progress = show_progress;
+        }
+        if let Some(open_in_external_app) = config.editor {
+            self.open_in_external_app = open_in_external_app;
+        }
+        if let Some(jump_to_related_locations) = config.jump_to_related {
+            self.jump_to_related_locations = jump_to_related_locations;
+        }
+        if let Some(publish_diagnostics) = config.publish_diagnostics {
+            self.publish_diagnostics = publish_diagnostics;
+        }
+        if let Some(nvim_modes) = config.nvim_modes {
+            self.nvim_modes = nvim_modes;
+        }
+        if let Some(nvim_instances) = config.nvim_instances {
+            self.nvim_instances = nvim_instances;
+        }
+        if let Some(report) = config.report {
+            self.report = report;
+        }
+        if let Some(report_format) = config.report_format {
+            self.report_format = report_format.parse()?;
+        }
+        if let Some(ci_annotations) = config.ci_annotations {
+            self.ci_annotations = ci_annotations.parse()?;
+        }
+        if let Some(hidden_lints) = config.hide_lints {
+            self.hidden_lints = LintPatterns::new(hidden_lints.iter().map(String::as_str))
+                .context("invalid hide-lints value")?;
+        }
+        if let Some(shown_lints) = config.show_lints {
+            self.shown_lints = LintPatterns::new(shown_lints.iter().map(String::as_str))
+                .context("invalid show-lints value")?;
+        }
+        Ok(())
+    }
+
+    pub fn is_lint_hidden(&self, code: &str) -> bool {
+        self.hidden_lints.matches(code) && !self.shown_lints.matches(code)
+    }
+
+    fn set_time_limit_after_error(&mut self, seconds: u64) {
+        let duration = Duration::from_secs(seconds);
+        self.time_limit_after_error = if duration > Duration::from_secs(0) {
+            Some(duration)
+        } else {
+            None
+        };
+    }
+
     fn process_args(
         mut self,
         current_exe: String,
@@ -142,9 +364,12 @@ impl Options {
         let ParsedSubcommand {
             subcommand,
             open_in_external_app_on_warnings,
+            watch,
             remaining_args,
         } = ParsedSubcommand::parse(args, current_exe)?;
         self.open_in_external_app_on_warnings = open_in_external_app_on_warnings;
+        self.watch = watch;
+        self.parse_test_failures = subcommand == "test";
 
         let mut args = remaining_args.into_iter();
         self.cargo_args.push(subcommand.clone());
@@ -211,6 +436,55 @@ impl Options {
                 } else if format == SHORT_FORMAT {
                     self.short_message_format = true;
                 }
+            } else if let Some(limit) = Self::parse_value(&arg, LIMIT_MESSAGES, passed_args)? {
+                self.limit_messages = limit;
+            } else if let Some(seconds) = Self::parse_value(&arg, TIME_LIMIT, passed_args)? {
+                self.set_time_limit_after_error(seconds);
+            } else if let Some(seconds) = Self::parse_value(&arg, INT_GRACE, passed_args)? {
+                self.interrupt_grace_period = Duration::from_secs(seconds);
+            } else if let Some(seconds) = Self::parse_value(&arg, TERM_GRACE, passed_args)? {
+                self.terminate_grace_period = Duration::from_secs(seconds);
+            } else if let Some(limit) = Self::parse_value(&arg, ERROR_LIMIT, passed_args)? {
+                self.error_limit = limit;
+            } else if let Some(value) = Self::parse_flag(&arg, STOP_ON_FAILED_CRATES)? {
+                self.stop_on_failed_crates = value;
+            } else if let Some(value) = Self::parse_flag(&arg, STOP_ON_MSG_LIMIT)? {
+                self.stop_on_messages_limit = value;
+            } else if let Some(value) = Self::parse_flag(&arg, STREAM)? {
+                self.stream_messages = value;
+            } else if let Some(value) = Self::parse_flag(&arg, STREAM_REPRINT)? {
+                self.reprint_streamed_messages = value;
+            } else if let Some(value) = Self::parse_flag(&arg, ASC)? {
+                self.ascending_messages_order = value;
+            } else if let Some(value) = Self::parse_flag(&arg, FORCE_WARN)? {
+                self.show_warnings_if_errors_exist = value;
+            } else if let Some(value) = Self::parse_flag(&arg, MEMBERS_WARN)? {
+                self.show_members_warnings = value;
+            } else if let Some(value) = Self::parse_flag(&arg, DEPS_WARN)? {
+                self.show_dependencies_warnings = value;
+            } else if let Some(value) = Self::parse_flag(&arg, REMOTE_DEPS_WARN)? {
+                self.show_remote_dependencies_warnings = value;
+            } else if let Some(value) = Self::parse_flag(&arg, SUMMARY)? {
+                self.show_summary = value;
+            } else if let Some(value) = Self::parse_flag(&arg, PROGRESS)? {
+                self.show_progress = value;
+            } else if let Some(app) = Self::parse_value(&arg, EDITOR, passed_args)? {
+                self.open_in_external_app = app;
+            } else if let Some(value) = Self::parse_flag(&arg, JUMP_TO_RELATED)? {
+                self.jump_to_related_locations = value;
+            } else if let Some(value) = Self::parse_flag(&arg, PUBLISH_DIAGNOSTICS)? {
+                self.publish_diagnostics = value;
+            } else if let Some(report) = Self::parse_value(&arg, REPORT, passed_args)? {
+                self.report = report;
+            } else if let Some(format) = Self::parse_value(&arg, REPORT_FORMAT, passed_args)? {
+                self.report_format = format;
+            } else if let Some(annotations) = Self::parse_value(&arg, CI_ANNOTATIONS, passed_args)?
+            {
+                self.ci_annotations = annotations;
+            } else if let Some(lints) = Self::parse_value(&arg, HIDE_LINTS, passed_args)? {
+                self.hidden_lints = lints;
+            } else if let Some(lints) = Self::parse_value(&arg, SHOW_LINTS, passed_args)? {
+                self.shown_lints = lints;
             } else if arg == APP_ARGS_DELIMITER {
                 *app_args_started = true;
                 break;
@@ -295,6 +569,43 @@ impl Options {
         Ok(())
     }
 
+    fn parse_value<T: FromStr>(
+        arg: &str,
+        option: &str,
+        passed_args: &mut impl Iterator<Item = String>,
+    ) -> Result<Option<T>>
+    where
+        <T as FromStr>::Err: std::error::Error + Sync + Send + 'static,
+    {
+        let name = &option[..option.len() - 1];
+        let value = if arg == name {
+            passed_args.next().with_context(|| {
+                format!("the argument '{name} <VALUE>' requires a value but none was supplied")
+            })?
+        } else if let Some(value) = arg.strip_prefix(option) {
+            value.to_owned()
+        } else {
+            return Ok(None);
+        };
+        let value = value
+            .parse()
+            .with_context(|| format!("invalid {name} value"))?;
+        Ok(Some(value))
+    }
+
+    fn parse_flag(arg: &str, flag: &str) -> Result<Option<bool>> {
+        if arg == flag {
+            Ok(Some(true))
+        } else if let Some(value) = arg.strip_prefix(flag).and_then(|i| i.strip_prefix('=')) {
+            let value = value
+                .parse()
+                .with_context(|| format!("invalid {flag} value"))?;
+            Ok(Some(value))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn validate_color(color: &str) -> Result<()> {
         if !VALID_COLORS.contains(&color) {
             return Err(format_err!(
@@ -357,9 +668,19 @@ impl ParsedSubcommand {
             }
         }
 
+        let watch = subcommand == WATCH_SUBCOMMAND;
+        let subcommand = if watch {
+            peekable_args
+                .next()
+                .context("subcommand to watch is missing")?
+        } else {
+            subcommand.to_owned()
+        };
+
         Ok(Self {
-            subcommand: subcommand.to_owned(),
+            subcommand,
             open_in_external_app_on_warnings,
+            watch,
             remaining_args: peekable_args.collect(),
         })
     }
@@ -369,6 +690,7 @@ impl ParsedSubcommand {
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
 
     const STUB_MINIMAL: &str = "minimal";
     const STUB_CUSTOM_TEST_RUNNER: &str = "custom_test_runner";
@@ -654,6 +976,7 @@ mod tests {
             vec!["--color=always"],
             Options {
                 json_message_format: true,
+                parse_test_failures: true,
                 ..Options::default()
             },
             STUB_MINIMAL,
@@ -665,6 +988,7 @@ mod tests {
             vec!["--color=always"],
             Options {
                 short_message_format: true,
+                parse_test_failures: true,
                 ..Options::default()
             },
             STUB_MINIMAL,
@@ -926,6 +1250,213 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn cargo_limit_args() -> Result<()> {
+        assert_options(
+            vec![
+                "cargo-lrun",
+                "--limit-messages",
+                "3",
+                "--time-limit=5",
+                "--int-grace=2",
+                "--term-grace",
+                "0",
+                "--error-limit",
+                "10",
+                "--stop-on-failed-crates",
+                "--stop-on-msg-limit=true",
+                "--stream",
+                "--stream-reprint=true",
+                "--asc",
+                "-v",
+                "--force-warn",
+                "--deps-warn=true",
+                "--members-warn=false",
+                "--remote-deps-warn",
+                "--editor",
+                "my-editor",
+                "--report=report.sarif",
+                "--report-format",
+                "sarif",
+                "--ci-annotations=github",
+                "--publish-diagnostics",
+                "--jump-to-related=true",
+                "--",
+                "--asc",
+            ],
+            vec![
+                "run",
+                "--message-format=json-diagnostic-rendered-ansi",
+                "-v",
+            ],
+            vec!["--asc"],
+            Options {
+                limit_messages: 3,
+                time_limit_after_error: Some(Duration::from_secs(5)),
+                interrupt_grace_period: Duration::from_secs(2),
+                terminate_grace_period: Duration::from_secs(0),
+                error_limit: 10,
+                stop_on_failed_crates: true,
+                stop_on_messages_limit: true,
+                stream_messages: true,
+                reprint_streamed_messages: true,
+                ascending_messages_order: true,
+                show_warnings_if_errors_exist: true,
+                show_members_warnings: false,
+                show_dependencies_warnings: true,
+                show_remote_dependencies_warnings: true,
+                open_in_external_app: "my-editor".to_owned(),
+                report: "report.sarif".to_owned(),
+                report_format: ReportFormat::Sarif,
+                ci_annotations: CiAnnotations::Github,
+                publish_diagnostics: true,
+                jump_to_related_locations: true,
+                ..Options::default()
+            },
+            STUB_MINIMAL,
+        )?;
+
+        assert_options(
+            vec![
+                "cargo-lcheck",
+                "--limit-messages=0",
+                "--time-limit",
+                "0",
+                "--asc=false",
+                "--summary=false",
+                "--progress=false",
+                "--editor=",
+            ],
+            vec!["check", "--message-format=json-diagnostic-rendered-ansi"],
+            vec![],
+            Options {
+                limit_messages: 0,
+                time_limit_after_error: None,
+                ascending_messages_order: false,
+                show_summary: false,
+                show_progress: false,
+                open_in_external_app: "".to_owned(),
+                ..Options::default()
+            },
+            STUB_MINIMAL,
+        )?;
+
+        assert!(Options::default()
+            .process_args(
+                "cargo-lcheck".to_owned(),
+                to_string(vec!["cargo-lcheck", "--limit-messages"]),
+                &stub_path(STUB_MINIMAL),
+            )
+            .is_err());
+
+        assert!(Options::default()
+            .process_args(
+                "cargo-lcheck".to_owned(),
+                to_string(vec!["cargo-lcheck", "--asc=yes"]),
+                &stub_path(STUB_MINIMAL),
+            )
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn lints_filter() -> Result<()> {
+        let mut options = Options::default();
+        assert!(!options.is_lint_hidden("dead_code"));
+
+        options.hidden_lints = "dead_code, clippy::*".parse()?;
+        options.shown_lints = "clippy::correctness_*".parse()?;
+        assert!(options.is_lint_hidden("dead_code"));
+        assert!(options.is_lint_hidden("clippy::needless_return"));
+        assert!(!options.is_lint_hidden("clippy::correctness_lint"));
+        assert!(!options.is_lint_hidden("unused_variables"));
+        assert!(!options.is_lint_hidden("E0308"));
+
+        assert_options(
+            vec!["cargo-lcheck", "--hide-lints", "dead_code,unused_*"],
+            vec!["check", "--message-format=json-diagnostic-rendered-ansi"],
+            vec![],
+            Options {
+                hidden_lints: LintPatterns::new(["dead_code", "unused_*"])?,
+                ..Options::default()
+            },
+            STUB_MINIMAL,
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn watch() -> Result<()> {
+        assert_options(
+            vec!["cargo-lwatch", "lwatch", "check", "--tests"],
+            vec![
+                "check",
+                "--message-format=json-diagnostic-rendered-ansi",
+                "--tests",
+            ],
+            vec![],
+            Options {
+                watch: true,
+                ..Options::default()
+            },
+            STUB_MINIMAL,
+        )?;
+
+        assert_options(
+            vec!["cargo-llwatch", "llwatch", "run", "--", "app-arg"],
+            vec!["run", "--message-format=json-diagnostic-rendered-ansi"],
+            vec!["app-arg"],
+            Options {
+                watch: true,
+                open_in_external_app_on_warnings: true,
+                ..Options::default()
+            },
+            STUB_MINIMAL,
+        )?;
+
+        assert!(ParsedSubcommand::parse(
+            to_string(vec!["cargo-lwatch", "lwatch"]),
+            "cargo-lwatch".to_owned()
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn apply_config() -> Result<()> {
+        let mut options = Options::default();
+        options.apply_config(Config {
+            msg_limit: Some(3),
+            time_limit: Some(0),
+            asc: Some(true),
+            deps_warn: Some(true),
+            editor: Some("".to_owned()),
+            hide_lints: Some(vec![
+                "dead_code".to_owned(),
+                "clippy::needless_*".to_owned(),
+            ]),
+            nvim_modes: Some("diagnostics,quickfix".to_owned()),
+            ..Config::default()
+        })?;
+        assert_eq!(
+            options,
+            Options {
+                limit_messages: 3,
+                time_limit_after_error: None,
+                ascending_messages_order: true,
+                show_dependencies_warnings: true,
+                open_in_external_app: "".to_owned(),
+                hidden_lints: LintPatterns::new(["dead_code", "clippy::needless_*"])?,
+                nvim_modes: "diagnostics,quickfix".to_owned(),
+                ..Options::default()
+            }
+        );
+        Ok(())
+    }
+
     #[test]
     fn parse_subcommand() -> Result<()> {
         assert_parse_subcommand(vec!["cargo-lrun"], "run", vec![])?;
@@ -974,11 +1505,15 @@ mod tests {
         expected_args_after_app_args_delimiter: Vec<&str>,
         stub: &str,
     ) -> Result<()> {
+        let expected_options = Options {
+            parse_test_failures: expected_cargo_args[0] == "test",
+            ..Options::default()
+        };
         assert_options(
             input,
             expected_cargo_args,
             expected_args_after_app_args_delimiter,
-            Default::default(),
+            expected_options,
             stub,
         )
     }
@@ -993,7 +1528,7 @@ mod tests {
         let options = Options::default().process_args(
             input[0].to_owned(),
             to_string(input),
-            &Path::new("tests/stubs").join(Path::new(stub)),
+            &stub_path(stub),
         )?;
 
         let expected = Options {
@@ -1024,12 +1559,17 @@ mod tests {
             ParsedSubcommand {
                 subcommand: expected_subcommand.to_owned(),
                 open_in_external_app_on_warnings: false,
+                watch: false,
                 remaining_args: expected_remaining_args,
             }
         );
         Ok(())
     }
 
+    fn stub_path(stub: &str) -> PathBuf {
+        Path::new("tests/stubs").join(Path::new(stub))
+    }
+
     fn to_string<'item>(
         iter: impl IntoIterator<Item = &'item str> + 'item,
     ) -> impl Iterator<Item = String> + 'item {
@@ -3,23 +3,44 @@ use anyhow::{Context, Result};
 use atomig::{Atom, Atomic};
 use std::{
     env, fmt,
+    io::{self, IsTerminal},
     path::PathBuf,
-    process::{Child, Command, Stdio},
-    sync::{atomic::Ordering, Arc},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, Once,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+
 pub(crate) const CARGO_EXECUTABLE: &str = "cargo";
 const CARGO_ENV_VAR: &str = "CARGO";
 
 #[doc(hidden)]
 pub const NO_EXIT_CODE: i32 = 127;
 
-#[derive(Debug)]
+const KILL_TIMEOUT: Duration = Duration::from_secs(1);
+const KILLING_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[cfg(unix)]
+const STAGES: &[Stage] = &[Stage::Interrupt, Stage::Terminate, Stage::Kill];
+
+#[cfg(windows)]
+const STAGES: &[Stage] = &[Stage::Interrupt, Stage::Kill];
+
+static CURRENT_PROCESS: Mutex<Option<Killer>> = Mutex::new(None);
+static CTRLC_HANDLER: Once = Once::new();
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
 pub struct CargoProcess {
-    child: Child,
-    state: Arc<Atomic<State>>,
+    buffers: Option<Buffers>,
+    exit: Arc<Exit>,
+    killer: Killer,
+    timed_out: Arc<AtomicBool>,
 }
 
 #[derive(Atom, Debug, Clone, Copy, PartialEq)]
@@ -32,6 +53,34 @@ pub enum State {
     FailedToKill,
 }
 
+/// Signal sent to cargo's process group, each next one is sent after a grace period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(windows, allow(dead_code))]
+pub enum Stage {
+    Interrupt,
+    Terminate,
+    Kill,
+}
+
+#[derive(Debug, Clone)]
+struct Killer {
+    pid: u32,
+    /// Cargo runs in the process group of this process if stdin is a terminal,
+    /// so the terminal could be read by the app run by cargo, and Ctrl+C reaches all of them.
+    own_process_group: bool,
+    state: Arc<Atomic<State>>,
+    exit: Arc<Exit>,
+    stopped_by: Arc<Mutex<Option<Stage>>>,
+    interrupt_grace_period: Duration,
+    terminate_grace_period: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Exit {
+    status: Mutex<Option<io::Result<ExitStatus>>>,
+    exited: Condvar,
+}
+
 trait StateExt {
     fn try_set_killing(&self) -> bool;
     fn try_set_start_kill_timer(&self) -> bool;
@@ -49,39 +98,78 @@ impl CargoProcess {
             .unwrap_or_else(|| PathBuf::from(CARGO_EXECUTABLE));
 
         let error_text = failed_to_execute_error_text(&cargo_path);
-        let child = Command::new(cargo_path)
-            .args(options.all_args())
-            .stdout(Stdio::piped())
-            .spawn()
-            .context(error_text)?;
+        let mut command = Command::new(cargo_path);
+        command.args(options.all_args()).stdout(Stdio::piped());
+        let own_process_group = cfg!(unix) && !io::stdin().is_terminal();
+        #[cfg(unix)]
+        if own_process_group {
+            command.process_group(0);
+        }
+        let mut child = command.spawn().context(error_text)?;
+        #[cfg(unix)]
+        if own_process_group {
+            job_control::forward_to(child.id());
+        }
+        let buffers = Buffers::new(&mut child)?;
 
         let state = Arc::new(Atomic::new(State::Running));
-        ctrlc::set_handler({
-            let pid = child.id();
-            let state = state.clone();
-            move || {
-                Self::kill(pid, state.clone());
-            }
-        })?;
+        let exit = Arc::new(Exit::default());
+        let killer = Killer {
+            pid: child.id(),
+            own_process_group,
+            state: state.clone(),
+            exit: exit.clone(),
+            stopped_by: Arc::new(Mutex::new(None)),
+            interrupt_grace_period: options.interrupt_grace_period,
+            terminate_grace_period: options.terminate_grace_period,
+        };
+        *CURRENT_PROCESS.lock().expect("poisoned mutex") = Some(killer.clone());
+        Self::wait_in_background(child, exit.clone());
+
+        let mut result = Ok(());
+        CTRLC_HANDLER.call_once(|| {
+            result = ctrlc::set_handler(|| {
+                INTERRUPTED.store(true, Ordering::Release);
+                Self::kill_current();
+            });
+        });
+        result?;
+
+        Ok(Self {
+            buffers: Some(buffers),
+            exit,
+            killer,
+            timed_out: Arc::new(AtomicBool::new(false)),
+        })
+    }
 
-        Ok(Self { child, state })
+    pub fn interrupted() -> bool {
+        INTERRUPTED.load(Ordering::Acquire)
+    }
+
+    /// Kills the most recently started process, if it's still running.
+    pub fn kill_current() {
+        let current_process = CURRENT_PROCESS.lock().expect("poisoned mutex").clone();
+        if let Some(killer) = current_process {
+            let _ = killer.kill();
+        }
     }
 
     pub fn buffers(&mut self) -> Result<Buffers> {
-        Buffers::new(&mut self.child)
+        self.buffers.take().context("stdout is already taken")
     }
 
     pub fn wait(&mut self) -> Result<i32> {
-        let exit_status = self.child.wait()?;
-        self.state.force_set_not_running();
+        let exit_status = self.exit.wait()?;
+        self.killer.state.force_set_not_running();
         Ok(exit_status.code().unwrap_or(NO_EXIT_CODE))
     }
 
     pub fn wait_if_killing_is_in_progress(&self) -> State {
         loop {
-            let state = self.state.load(Ordering::Acquire);
+            let state = self.killer.state.load(Ordering::Acquire);
             if state == State::Killing {
-                thread::yield_now();
+                thread::sleep(KILLING_POLL_INTERVAL);
             } else {
                 break state;
             }
@@ -89,49 +177,188 @@ impl CargoProcess {
     }
 
     pub fn kill_after_timeout(&self, time_limit: Duration) {
-        if self.state.try_set_start_kill_timer() {
+        if self.killer.state.try_set_start_kill_timer() {
             thread::spawn({
-                let pid = self.child.id();
-                let state = self.state.clone();
+                let killer = self.killer.clone();
+                let timed_out = self.timed_out.clone();
                 move || {
                     thread::sleep(time_limit);
-                    Self::kill(pid, state);
+                    if killer.kill() {
+                        timed_out.store(true, Ordering::Release);
+                    }
                 }
             });
         }
     }
 
-    fn kill(pid: u32, state: Arc<Atomic<State>>) {
-        if state.try_set_killing() {
-            let success = {
-                #[cfg(unix)]
-                unsafe {
-                    libc::kill(pid as libc::pid_t, libc::SIGINT) == 0
-                }
+    /// Kills the process without waiting for the time limit, even if its timer is already started.
+    pub fn kill_now(&self) {
+        thread::spawn({
+            let killer = self.killer.clone();
+            move || {
+                let _ = killer.kill();
+            }
+        });
+    }
 
-                #[cfg(windows)]
-                {
-                    use std::process::Output;
-                    if let Ok(Output { stderr, .. }) = Command::new("taskkill")
-                        .args(["/PID", pid.to_string().as_str(), "/t"])
-                        .output()
-                    {
-                        stderr.starts_with(b"SUCCESS")
-                    } else {
-                        false
+    pub fn timed_out(&self) -> bool {
+        self.timed_out.load(Ordering::Acquire)
+    }
+
+    /// Signal which has actually stopped the process, if it was killed.
+    pub fn stopped_by(&self) -> Option<Stage> {
+        *self.killer.stopped_by.lock().expect("poisoned mutex")
+    }
+
+    /// Waits for exit in a separate thread, so killing could wait for it with a timeout.
+    fn wait_in_background(mut child: Child, exit: Arc<Exit>) {
+        thread::spawn(move || exit.set(child.wait()));
+    }
+}
+
+impl Killer {
+    /// Returns `true` if the process was killed by this call.
+    fn kill(&self) -> bool {
+        if self.state.try_set_killing() {
+            let stopped_by = STAGES.iter().copied().find(|&stage| {
+                stage.send(self.pid, self.own_process_group)
+                    && self.wait_for_stop(self.grace_period(stage))
+            });
+            *self.stopped_by.lock().expect("poisoned mutex") = stopped_by;
+
+            if stopped_by.is_some() {
+                self.state.set_not_running();
+                true
+            } else {
+                self.state.set_failed_to_kill();
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Returns `true` if cargo has exited and all processes of its own group are gone.
+    fn wait_for_stop(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        self.exit.wait_timeout(timeout)
+            && (!self.own_process_group
+                || loop {
+                    if !process_group_exists(self.pid) {
+                        break true;
+                    } else if Instant::now() >= deadline {
+                        break false;
                     }
-                }
+                    thread::sleep(KILLING_POLL_INTERVAL);
+                })
+    }
 
-                #[cfg(not(any(unix, windows)))]
-                compile_error!("this platform is unsupported");
+    fn grace_period(&self, stage: Stage) -> Duration {
+        match stage {
+            Stage::Interrupt => self.interrupt_grace_period,
+            Stage::Terminate => self.terminate_grace_period,
+            Stage::Kill => KILL_TIMEOUT,
+        }
+    }
+}
+
+impl Stage {
+    /// Returns `true` if the signal was sent.
+    fn send(self, pid: u32, own_process_group: bool) -> bool {
+        #[cfg(unix)]
+        {
+            let signal = match self {
+                Self::Interrupt => libc::SIGINT,
+                Self::Terminate => libc::SIGTERM,
+                Self::Kill => libc::SIGKILL,
             };
+            let pid = pid as libc::pid_t;
+            let target = if own_process_group { -pid } else { pid };
+            unsafe { libc::kill(target, signal) == 0 }
+        }
 
-            if success {
-                state.set_not_running()
+        #[cfg(windows)]
+        {
+            use std::process::Output;
+            let _ = own_process_group;
+            let mut command = Command::new("taskkill");
+            command.args(["/PID", pid.to_string().as_str(), "/t"]);
+            if self == Self::Kill {
+                command.arg("/f");
+            }
+            if let Ok(Output { stderr, .. }) = command.output() {
+                stderr.starts_with(b"SUCCESS")
             } else {
-                state.set_failed_to_kill()
+                false
             }
         }
+
+        #[cfg(not(any(unix, windows)))]
+        compile_error!("this platform is unsupported");
+    }
+}
+
+/// Cargo is already reaped, so only lingering processes like rustc or build scripts are checked.
+fn process_group_exists(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        let exists = unsafe { libc::kill(-(pid as libc::pid_t), 0) } == 0;
+        exists || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = pid;
+        false
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = {
+            #[cfg(unix)]
+            match self {
+                Self::Interrupt => "SIGINT",
+                Self::Terminate => "SIGTERM",
+                Self::Kill => "SIGKILL",
+            }
+
+            #[cfg(windows)]
+            match self {
+                Self::Interrupt | Self::Terminate => "taskkill",
+                Self::Kill => "taskkill /f",
+            }
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Exit {
+    fn set(&self, status: io::Result<ExitStatus>) {
+        *self.status.lock().expect("poisoned mutex") = Some(status);
+        self.exited.notify_all();
+    }
+
+    /// Returns `true` if the process has exited within the timeout.
+    fn wait_timeout(&self, timeout: Duration) -> bool {
+        let status = self.status.lock().expect("poisoned mutex");
+        let (status, _) = self
+            .exited
+            .wait_timeout_while(status, timeout, |status| status.is_none())
+            .expect("poisoned mutex");
+        status.is_some()
+    }
+
+    fn wait(&self) -> io::Result<ExitStatus> {
+        let status = self.status.lock().expect("poisoned mutex");
+        let status = self
+            .exited
+            .wait_while(status, |status| status.is_none())
+            .expect("poisoned mutex");
+        match status.as_ref().expect("no exit status") {
+            Ok(status) => Ok(*status),
+            Err(err) => Err(io::Error::new(err.kind(), err.to_string())),
+        }
     }
 }
 
@@ -166,3 +393,42 @@ impl StateExt for Arc<Atomic<State>> {
 pub(crate) fn failed_to_execute_error_text<T: fmt::Debug>(app: T) -> String {
     format!("failed to execute {app:?}")
 }
+
+/// Cargo which runs in its own process group is killed along with its children,
+/// while job control signals are sent to this process only.
+/// Interruption is handled by `Killer::kill`, stopping and continuing are forwarded here.
+#[cfg(unix)]
+mod job_control {
+    use std::sync::{
+        atomic::{AtomicI32, Ordering},
+        Once,
+    };
+
+    static PROCESS_GROUP: AtomicI32 = AtomicI32::new(0);
+    static HANDLERS: Once = Once::new();
+
+    pub fn forward_to(process_group: u32) {
+        PROCESS_GROUP.store(process_group as libc::pid_t, Ordering::Release);
+        HANDLERS.call_once(|| unsafe {
+            libc::signal(libc::SIGTSTP, on_stop as libc::sighandler_t);
+            libc::signal(libc::SIGCONT, on_continue as libc::sighandler_t);
+        });
+    }
+
+    /// Stops the process group, then stops itself, since `SIGTSTP` is handled instead.
+    extern "C" fn on_stop(_: libc::c_int) {
+        forward(libc::SIGTSTP);
+        unsafe { libc::raise(libc::SIGSTOP) };
+    }
+
+    extern "C" fn on_continue(_: libc::c_int) {
+        forward(libc::SIGCONT);
+    }
+
+    fn forward(signal: libc::c_int) {
+        let process_group = PROCESS_GROUP.load(Ordering::Acquire);
+        if process_group != 0 {
+            unsafe { libc::kill(-process_group, signal) };
+        }
+    }
+}
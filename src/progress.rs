@@ -0,0 +1,82 @@
+use crate::{io::Buffers, options::Options, workspace::Workspace};
+use cargo_metadata::Message;
+use std::io::{self, IsTerminal};
+
+pub struct Progress {
+    total_units: Option<usize>,
+    finished_units: usize,
+}
+
+impl Progress {
+    /// Returns `None` if progress is disabled or stderr is not a terminal.
+    pub fn new(options: &Options, workspace: &Workspace) -> Option<Self> {
+        if !options.show_progress || options.help || options.version || !io::stderr().is_terminal()
+        {
+            return None;
+        }
+
+        Some(Self {
+            total_units: workspace.total_units(),
+            finished_units: 0,
+        })
+    }
+
+    pub fn update(&mut self, buffers: &mut Buffers, message: &Message) -> io::Result<()> {
+        let name = match message {
+            Message::CompilerArtifact(artifact) => artifact.target.name.as_str(),
+            Message::BuildScriptExecuted(build_script) => {
+                package_name(&build_script.package_id.repr)
+            },
+            _ => return Ok(()),
+        };
+        self.finished_units += 1;
+
+        let line = match self.total_units {
+            Some(total_units) => {
+                let total_units = total_units.max(self.finished_units);
+                format!("compiling {}/{total_units}: {name}", self.finished_units)
+            },
+            None => format!("compiling {}: {name}", self.finished_units),
+        };
+        buffers.write_progress(&line)
+    }
+}
+
+/// Package id is like "name version (source)", "source#name@version" or "source/name#version",
+/// depending on cargo version.
+fn package_name(package_id: &str) -> &str {
+    if let Some((source, name_and_version)) = package_id.rsplit_once('#') {
+        match name_and_version.split_once('@') {
+            Some((name, _version)) => name,
+            None => {
+                let path = source.split('?').next().unwrap_or(source);
+                path.rsplit('/').next().unwrap_or(path)
+            },
+        }
+    } else {
+        package_id.split(' ').next().unwrap_or(package_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn package_names() {
+        assert_eq!(package_name("path+file:///workspace/app#0.1.0"), "app");
+        assert_eq!(
+            package_name("app 0.1.0 (path+file:///workspace/app)"),
+            "app"
+        );
+        assert_eq!(
+            package_name("registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0"),
+            "serde"
+        );
+        assert_eq!(
+            package_name("git+https://github.com/user/lib?branch=main#0123456789abcdef"),
+            "lib"
+        );
+    }
+}
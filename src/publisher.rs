@@ -0,0 +1,352 @@
+use crate::{
+    models::{EditorData, Location},
+    opener::listen_address,
+    report::file_uri,
+};
+use anyhow::{Context, Result};
+use cargo_metadata::diagnostic::DiagnosticLevel;
+use itertools::Itertools;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind, Write},
+    iter,
+    path::{Path, PathBuf},
+};
+
+const PREFIX: &str = "lsp-cargo-limit-";
+const JSONRPC_VERSION: &str = "2.0";
+const CLEAR_DIAGNOSTICS_METHOD: &str = "cargo-limit/clearDiagnostics";
+const PUBLISH_DIAGNOSTICS_METHOD: &str = "textDocument/publishDiagnostics";
+const SOURCE: &str = "rustc";
+
+/// Publishes locations to an editor extension listening to the workspace socket, if any.
+pub fn publish(editor_data: &EditorData) -> Result<()> {
+    let address = listen_address(PREFIX, &editor_data.escaped_workspace_root())?;
+    publish_to(&address, editor_data)
+}
+
+fn publish_to(address: &str, editor_data: &EditorData) -> Result<()> {
+    match connect(address) {
+        Ok(mut stream) => {
+            for notification in notifications(editor_data) {
+                let content = serde_json::to_string(&notification)?;
+                write!(stream, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+            }
+            stream.flush()?;
+            Ok(())
+        },
+        Err(err)
+            if matches!(
+                err.kind(),
+                ErrorKind::NotFound | ErrorKind::ConnectionRefused
+            ) =>
+        {
+            Ok(())
+        },
+        Err(err) => {
+            Err(err).with_context(|| format!("failed to publish diagnostics to {address:?}"))
+        },
+    }
+}
+
+#[cfg(unix)]
+fn connect(address: &str) -> io::Result<impl Write> {
+    std::os::unix::net::UnixStream::connect(address)
+}
+
+#[cfg(windows)]
+fn connect(address: &str) -> io::Result<impl Write> {
+    std::fs::OpenOptions::new().write(true).open(address)
+}
+
+/// Clears diagnostics of the previous run, then publishes them for each affected file.
+fn notifications(editor_data: &EditorData) -> Vec<Value> {
+    let clear = notification(
+        CLEAR_DIAGNOSTICS_METHOD,
+        json!({ "workspaceRoot": file_uri(editor_data.workspace_root()) }),
+    );
+    let mut sources = Sources::default();
+    let publish = editor_data
+        .locations()
+        .iter()
+        .into_group_map_by(|location| location.path())
+        .into_iter()
+        .sorted_by(|(left, _), (right, _)| left.cmp(right))
+        .map(|(path, locations)| {
+            let diagnostics = locations
+                .into_iter()
+                .map(|location| diagnostic(location, &mut sources))
+                .collect::<Vec<_>>();
+            notification(
+                PUBLISH_DIAGNOSTICS_METHOD,
+                json!({ "uri": file_uri(path), "diagnostics": diagnostics }),
+            )
+        });
+    iter::once(clear).chain(publish).collect()
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({
+        "jsonrpc": JSONRPC_VERSION,
+        "method": method,
+        "params": params,
+    })
+}
+
+fn diagnostic(location: &Location, sources: &mut Sources) -> Value {
+    let path = location.path();
+    let start = sources.position(path, location.line(), location.column());
+    let end = if location.line_end() == 0 {
+        start.clone()
+    } else {
+        sources.position(path, location.line_end(), location.column_end())
+    };
+    let mut result = json!({
+        "range": { "start": start, "end": end },
+        "severity": severity(location.level()),
+        "source": SOURCE,
+        "message": location.message(),
+    });
+    if let Some(code) = location.code() {
+        result["code"] = json!(code);
+    }
+    let related_information = location
+        .related()
+        .iter()
+        .map(|related| {
+            let path = related.path();
+            json!({
+                "location": {
+                    "uri": file_uri(path),
+                    "range": {
+                        "start": sources.position(path, related.line(), related.column()),
+                        "end": sources.position(path, related.line_end(), related.column_end()),
+                    },
+                },
+                "message": related.label().unwrap_or_default(),
+            })
+        })
+        .collect::<Vec<_>>();
+    if !related_information.is_empty() {
+        result["relatedInformation"] = json!(related_information);
+    }
+    result
+}
+
+/// Lines of affected files, since LSP counts characters in UTF-16 code units,
+/// while compiler counts them in code points.
+#[derive(Default)]
+struct Sources(HashMap<PathBuf, Option<Vec<String>>>);
+
+impl Sources {
+    fn position(&mut self, path: &Path, line: usize, column: usize) -> Value {
+        json!({
+            "line": line.saturating_sub(1),
+            "character": utf16_character(self.line(path, line), column),
+        })
+    }
+
+    fn line(&mut self, path: &Path, line: usize) -> Option<&str> {
+        let lines = self.0.entry(path.to_path_buf()).or_insert_with(|| {
+            fs::read_to_string(path)
+                .ok()
+                .map(|text| text.lines().map(str::to_owned).collect())
+        });
+        lines
+            .as_ref()?
+            .get(line.checked_sub(1)?)
+            .map(String::as_str)
+    }
+}
+
+/// Code point column is used as is if the file can't be read.
+fn utf16_character(line: Option<&str>, column: usize) -> usize {
+    let code_points = column.saturating_sub(1);
+    line.map_or(code_points, |line| {
+        line.chars().take(code_points).map(char::len_utf16).sum()
+    })
+}
+
+fn severity(level: DiagnosticLevel) -> u8 {
+    match level {
+        DiagnosticLevel::Ice | DiagnosticLevel::Error => 1,
+        DiagnosticLevel::Warning => 2,
+        DiagnosticLevel::Help => 4,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::project_primary_spans;
+    use cargo_metadata::diagnostic::Diagnostic;
+    use pretty_assertions::assert_eq;
+    use std::path::Path;
+
+    #[cfg(unix)]
+    #[test]
+    fn local_socket_client() -> Result<()> {
+        use std::{io::Read, os::unix::net::UnixListener};
+
+        let dir = tempfile::tempdir()?;
+        let address = dir.path().join("socket").to_string_lossy().into_owned();
+        let listener = UnixListener::bind(&address)?;
+
+        publish_to(&address, &editor_data()?)?;
+        let mut received = String::new();
+        listener.accept()?.0.read_to_string(&mut received)?;
+
+        let mut notifications = Vec::new();
+        let mut rest = received.as_str();
+        while let Some((header, tail)) = rest.split_once("\r\n\r\n") {
+            let length = header
+                .strip_prefix("Content-Length: ")
+                .context("invalid header")?
+                .parse::<usize>()?;
+            let (content, tail) = tail.split_at(length);
+            notifications.push(serde_json::from_str::<Value>(content)?);
+            rest = tail;
+        }
+        assert_eq!(rest, "");
+        assert_eq!(notifications, super::notifications(&editor_data()?));
+        Ok(())
+    }
+
+    #[test]
+    fn utf16_characters() {
+        assert_eq!(utf16_character(Some("let x = 1;"), 5), 4);
+        assert_eq!(utf16_character(Some("let ё = \"🦀\";"), 10), 9);
+        assert_eq!(utf16_character(Some("let ё = \"🦀\";"), 11), 11);
+        assert_eq!(utf16_character(None, 11), 10);
+    }
+
+    #[test]
+    fn notifications() -> Result<()> {
+        let notifications = super::notifications(&editor_data()?);
+        assert_eq!(
+            notifications,
+            vec![
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "cargo-limit/clearDiagnostics",
+                    "params": { "workspaceRoot": "file:///workspace" },
+                }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": {
+                        "uri": "file:///workspace/src/main.rs",
+                        "diagnostics": [
+                            {
+                                "range": {
+                                    "start": { "line": 4, "character": 4 },
+                                    "end": { "line": 4, "character": 13 },
+                                },
+                                "severity": 1,
+                                "source": "rustc",
+                                "code": "E0502",
+                                "relatedInformation": [
+                                    {
+                                        "location": {
+                                            "uri": "file:///workspace/src/main.rs",
+                                            "range": {
+                                                "start": { "line": 3, "character": 17 },
+                                                "end": { "line": 3, "character": 18 },
+                                            },
+                                        },
+                                        "message": "immutable borrow occurs here",
+                                    },
+                                    {
+                                        "location": {
+                                            "uri": "file:///workspace/src/main.rs",
+                                            "range": {
+                                                "start": { "line": 5, "character": 4 },
+                                                "end": { "line": 5, "character": 25 },
+                                            },
+                                        },
+                                        "message": "immutable borrow later used here",
+                                    },
+                                ],
+                                "message": "cannot borrow `v` as mutable because it is also \
+                                            borrowed as immutable",
+                            },
+                            {
+                                "range": {
+                                    "start": { "line": 1, "character": 8 },
+                                    "end": { "line": 1, "character": 14 },
+                                },
+                                "severity": 2,
+                                "source": "rustc",
+                                "code": "unused_variables",
+                                "message": "unused variable: `unused`",
+                            },
+                        ],
+                    },
+                }),
+            ]
+        );
+        Ok(())
+    }
+
+    fn editor_data() -> Result<EditorData> {
+        let span = |line: usize, columns: (usize, usize), label: Option<&str>| {
+            json!({
+                "file_name": "src/main.rs",
+                "byte_start": 0,
+                "byte_end": 1,
+                "line_start": line,
+                "line_end": line,
+                "column_start": columns.0,
+                "column_end": columns.1,
+                "is_primary": label.is_none(),
+                "text": [],
+                "label": label,
+                "suggested_replacement": null,
+                "suggestion_applicability": null,
+                "expansion": null,
+            })
+        };
+        let diagnostic = |level: &str, code: &str, message: &str, spans: Vec<Value>| {
+            serde_json::from_value::<Diagnostic>(json!({
+                "message": message,
+                "code": { "code": code, "explanation": null },
+                "level": level,
+                "spans": spans,
+                "children": [],
+                "rendered": null,
+            }))
+        };
+        let diagnostics = [
+            diagnostic(
+                "error",
+                "E0502",
+                "cannot borrow `v` as mutable because it is also borrowed as immutable",
+                vec![
+                    span(5, (5, 14), None),
+                    span(4, (18, 19), Some("immutable borrow occurs here")),
+                    span(6, (5, 26), Some("immutable borrow later used here")),
+                ],
+            )?,
+            diagnostic(
+                "warning",
+                "unused_variables",
+                "unused variable: `unused`",
+                vec![span(2, (9, 15), None)],
+            )?,
+        ];
+
+        let workspace_root = Path::new("/workspace");
+        let locations = diagnostics
+            .iter()
+            .flat_map(|diagnostic| {
+                project_primary_spans(diagnostic)
+                    .map(|span| Location::new(span, diagnostic, workspace_root))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        Ok(EditorData::new(workspace_root, locations))
+    }
+}
@@ -0,0 +1,510 @@
+mod checkstyle;
+mod github;
+mod gitlab;
+mod junit;
+mod rdjson;
+mod sarif;
+
+use crate::{
+    io::Buffers,
+    models::{find_leaf_project_expansion, strip_ansi_escape_codes, Report},
+};
+use anyhow::{Context, Result};
+use cargo_metadata::{
+    diagnostic::{Diagnostic, DiagnosticSpan},
+    CompilerMessage, Message,
+};
+use std::{
+    env,
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const JSON_FORMAT: &str = "json";
+const SARIF_FORMAT: &str = "sarif";
+const JUNIT_FORMAT: &str = "junit";
+const GITHUB_FORMAT: &str = "github";
+const GITLAB_FORMAT: &str = "gitlab";
+const CHECKSTYLE_FORMAT: &str = "checkstyle";
+const RDJSON_FORMAT: &str = "rdjson";
+const RDJSONL_FORMAT: &str = "rdjsonl";
+const VALID_FORMATS: &[&str] = &[
+    JSON_FORMAT,
+    SARIF_FORMAT,
+    JUNIT_FORMAT,
+    GITHUB_FORMAT,
+    GITLAB_FORMAT,
+    CHECKSTYLE_FORMAT,
+    RDJSON_FORMAT,
+    RDJSONL_FORMAT,
+];
+
+const AUTO_ANNOTATIONS: &str = "auto";
+const NO_ANNOTATIONS: &str = "none";
+const VALID_ANNOTATIONS: &[&str] = &[
+    AUTO_ANNOTATIONS,
+    GITHUB_FORMAT,
+    GITLAB_FORMAT,
+    NO_ANNOTATIONS,
+];
+
+const GITLAB_CODE_QUALITY_REPORT: &str = "gl-code-quality-report.json";
+
+const CLIPPY_PREFIX: &str = "clippy::";
+const CLIPPY_LINTS_URI: &str = "https://rust-lang.github.io/rust-clippy/master/index.html";
+const ERROR_CODES_URI: &str = "https://doc.rust-lang.org/error_codes";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Sarif,
+    Junit,
+    Github,
+    Gitlab,
+    Checkstyle,
+    Rdjson,
+    Rdjsonl,
+}
+
+/// Annotations of CI pull/merge requests.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CiAnnotations {
+    #[default]
+    Auto,
+    Github,
+    Gitlab,
+    None,
+}
+
+#[derive(Debug)]
+pub struct InvalidVariant {
+    name: &'static str,
+    valid_variants: &'static [&'static str],
+    value: String,
+}
+
+impl ReportFormat {
+    pub fn write(self, path: &Path, report: &Report) -> Result<()> {
+        fs::write(path, self.render(report)?)
+            .with_context(|| format!("failed to write report to {path:?}"))
+    }
+
+    fn render(self, report: &Report) -> Result<String> {
+        let report = match self {
+            Self::Json => serde_json::to_string_pretty(report)?,
+            Self::Sarif => serde_json::to_string_pretty(&sarif::log(report))?,
+            Self::Junit => junit::xml(report),
+            Self::Github => github::workflow_commands(report),
+            Self::Gitlab => serde_json::to_string_pretty(&gitlab::code_quality(report))?,
+            Self::Checkstyle => checkstyle::xml(report),
+            Self::Rdjson => serde_json::to_string_pretty(&rdjson::result(report))?,
+            Self::Rdjsonl => rdjson::diagnostics(report)
+                .iter()
+                .map(|diagnostic| serde_json::to_string(diagnostic).map(|line| line + "\n"))
+                .collect::<Result<String, _>>()?,
+        };
+        Ok(report)
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = InvalidVariant;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            JSON_FORMAT => Ok(Self::Json),
+            SARIF_FORMAT => Ok(Self::Sarif),
+            JUNIT_FORMAT => Ok(Self::Junit),
+            GITHUB_FORMAT => Ok(Self::Github),
+            GITLAB_FORMAT => Ok(Self::Gitlab),
+            CHECKSTYLE_FORMAT => Ok(Self::Checkstyle),
+            RDJSON_FORMAT => Ok(Self::Rdjson),
+            RDJSONL_FORMAT => Ok(Self::Rdjsonl),
+            _ => Err(InvalidVariant::new("report format", VALID_FORMATS, format)),
+        }
+    }
+}
+
+impl CiAnnotations {
+    pub fn detect(self, json_message_format: bool) -> Self {
+        let is_true = |var| env::var(var).map_or(false, |i| i == "true");
+        let ci = if is_true("GITHUB_ACTIONS") {
+            Self::Github
+        } else if is_true("GITLAB_CI") {
+            Self::Gitlab
+        } else {
+            Self::None
+        };
+        self.resolve(ci, json_message_format)
+    }
+
+    /// GitHub workflow commands are skipped with JSON message format, since they would break the JSON stream.
+    fn resolve(self, ci: Self, json_message_format: bool) -> Self {
+        match self {
+            Self::Auto => ci.resolve(Self::None, json_message_format),
+            Self::Github if json_message_format => Self::None,
+            _ => self,
+        }
+    }
+
+    pub fn write(self, buffers: &mut Buffers, report: &Report) -> Result<()> {
+        match self {
+            Self::Github => buffers.write_to_stdout(&ReportFormat::Github.render(report)?)?,
+            Self::Gitlab => ReportFormat::Gitlab.write(
+                &report.workspace_root.join(GITLAB_CODE_QUALITY_REPORT),
+                report,
+            )?,
+            Self::Auto | Self::None => (),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CiAnnotations {
+    type Err = InvalidVariant;
+
+    fn from_str(annotations: &str) -> Result<Self, Self::Err> {
+        match annotations {
+            AUTO_ANNOTATIONS => Ok(Self::Auto),
+            GITHUB_FORMAT => Ok(Self::Github),
+            GITLAB_FORMAT => Ok(Self::Gitlab),
+            NO_ANNOTATIONS => Ok(Self::None),
+            _ => Err(InvalidVariant::new(
+                "CI annotations",
+                VALID_ANNOTATIONS,
+                annotations,
+            )),
+        }
+    }
+}
+
+impl InvalidVariant {
+    fn new(name: &'static str, valid_variants: &'static [&'static str], value: &str) -> Self {
+        Self {
+            name,
+            valid_variants,
+            value: value.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} must be {} (was {})",
+            self.name,
+            self.valid_variants.join(", "),
+            self.value
+        )
+    }
+}
+
+impl Error for InvalidVariant {}
+
+fn compiler_messages(report: &Report) -> impl Iterator<Item = &CompilerMessage> {
+    report.messages.iter().filter_map(|message| match message {
+        Message::CompilerMessage(compiler_message) => Some(compiler_message),
+        _ => None,
+    })
+}
+
+/// Returns first primary span, pointing to macro call site in case of macro expansion.
+fn primary_span(diagnostic: &Diagnostic) -> Option<DiagnosticSpan> {
+    diagnostic
+        .spans
+        .iter()
+        .find(|span| span.is_primary)
+        .cloned()
+        .map(find_leaf_project_expansion)
+}
+
+fn help_uri(code: &str) -> Option<String> {
+    if let Some(lint) = code.strip_prefix(CLIPPY_PREFIX) {
+        Some(format!("{CLIPPY_LINTS_URI}#{lint}"))
+    } else if code.starts_with('E') && code[1..].chars().all(|i| i.is_ascii_digit()) {
+        Some(format!("{ERROR_CODES_URI}/{code}.html"))
+    } else {
+        None
+    }
+}
+
+/// Returns path relative to repository root from environment variable, if workspace is inside of it.
+fn repository_path(workspace_root: &Path, repository_root_var: &str, file_name: &str) -> String {
+    let repository_root = env::var_os(repository_root_var).map(PathBuf::from);
+    let repository_root = repository_root.as_deref().unwrap_or(workspace_root);
+    let path = workspace_root.join(file_name);
+    path.strip_prefix(repository_root)
+        .unwrap_or(Path::new(file_name))
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Returns path relative to workspace root, if possible.
+fn relative_path(workspace_root: &Path, file_name: &str) -> PathBuf {
+    let path = Path::new(file_name);
+    path.strip_prefix(workspace_root)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+pub(crate) fn file_uri(path: &Path) -> String {
+    let path = encode_path(path);
+    if path.starts_with('/') {
+        format!("file://{path}")
+    } else {
+        format!("file:///{path}")
+    }
+}
+
+fn encode_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut result = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~:".contains(&byte) {
+            result.push(char::from(byte));
+        } else {
+            result.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    result
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in strip_ansi_escape_codes(text).chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            '\t' | '\n' | '\r' => result.push(c),
+            c if c.is_control() => (),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Summary;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::{fs, time::Duration};
+
+    fn report() -> Report {
+        let messages = fs::read_to_string("tests/stubs/compiler_messages.jsonl")
+            .expect("failed to read stub")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("invalid stub"))
+            .collect();
+        Report::new(
+            Path::new("/workspace"),
+            101,
+            false,
+            Duration::from_millis(1500),
+            Summary::default(),
+            messages,
+        )
+    }
+
+    #[test]
+    fn rendering() -> Result<()> {
+        let report = report();
+
+        let sarif = sarif::log(&report);
+        let error = &sarif["runs"][0]["results"][0];
+        assert_eq!(
+            (
+                &error["ruleId"],
+                &error["locations"][0]["physicalLocation"]["region"],
+                &error["relatedLocations"][1]["physicalLocation"]["region"]["startLine"],
+            ),
+            (
+                &json!("E0502"),
+                &json!({
+                    "startLine": 5,
+                    "startColumn": 5,
+                    "endLine": 5,
+                    "endColumn": 14,
+                    "byteOffset": 84,
+                    "byteLength": 9,
+                }),
+                &json!(6),
+            )
+        );
+
+        let junit = junit::xml(&report);
+        assert_eq!(
+            junit.lines().nth(1),
+            Some(
+                r#"<testsuites name="cargo-limit" tests="2" failures="1" skipped="1" errors="0" time="1.5">"#
+            )
+        );
+
+        let checkstyle = checkstyle::xml(&report);
+        assert_eq!(
+            checkstyle
+                .lines()
+                .filter(|i| i.contains("<error "))
+                .collect::<Vec<_>>(),
+            vec![
+                r#"    <error line="5" column="5" severity="error" message="cannot borrow `v` as mutable because it is also borrowed as immutable" source="E0502"/>"#,
+                r#"    <error line="2" column="9" severity="warning" message="unused variable: `unused`" source="unused_variables"/>"#,
+            ]
+        );
+
+        let rdjson = rdjson::result(&report);
+        assert_eq!(
+            rdjson["diagnostics"][1]["suggestions"],
+            json!([{
+                "range": {
+                    "start": { "line": 2, "column": 9 },
+                    "end": { "line": 2, "column": 15 },
+                },
+                "text": "_unused",
+            }])
+        );
+
+        let github = github::workflow_commands(&report);
+        assert_eq!(
+            github
+                .lines()
+                .map(|i| i.split("::").nth(1).unwrap_or_default())
+                .collect::<Vec<_>>(),
+            vec![
+                "error file=src/main.rs,line=5,col=5,endLine=5,endColumn=14,title=E0502",
+                "warning file=src/main.rs,line=2,col=9,endLine=2,endColumn=15,title=unused_variables",
+            ]
+        );
+
+        let gitlab = gitlab::code_quality(&report);
+        assert_eq!(
+            gitlab
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|i| (
+                    &i["check_name"],
+                    &i["severity"],
+                    &i["location"]["lines"]["begin"]
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (&json!("E0502"), &json!("critical"), &json!(5)),
+                (&json!("unused_variables"), &json!("minor"), &json!(2)),
+            ]
+        );
+
+        let json = ReportFormat::Json.render(&report)?;
+        assert!(json.contains(r#""exit_code": 101"#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn report_formats() {
+        assert_eq!(
+            "json".parse::<ReportFormat>().ok(),
+            Some(ReportFormat::Json)
+        );
+        assert_eq!(
+            "sarif".parse::<ReportFormat>().ok(),
+            Some(ReportFormat::Sarif)
+        );
+        let error = "xml"
+            .parse::<ReportFormat>()
+            .expect_err("invalid format is accepted");
+        assert_eq!(
+            error.to_string(),
+            "report format must be json, sarif, junit, github, gitlab, checkstyle, rdjson, rdjsonl \
+             (was xml)"
+        );
+    }
+
+    #[test]
+    fn ci_annotations() {
+        assert_eq!(
+            "none".parse::<CiAnnotations>().ok(),
+            Some(CiAnnotations::None)
+        );
+        let none = CiAnnotations::None;
+        assert_eq!(
+            CiAnnotations::Github.resolve(none, false),
+            CiAnnotations::Github
+        );
+        assert_eq!(CiAnnotations::Github.resolve(none, true), none);
+        assert_eq!(
+            CiAnnotations::Gitlab.resolve(none, true),
+            CiAnnotations::Gitlab
+        );
+        assert_eq!(none.resolve(CiAnnotations::Github, false), none);
+        assert_eq!(
+            CiAnnotations::Auto.resolve(CiAnnotations::Gitlab, false),
+            CiAnnotations::Gitlab
+        );
+        assert_eq!(
+            CiAnnotations::Auto.resolve(CiAnnotations::Github, false),
+            CiAnnotations::Github
+        );
+        assert_eq!(
+            CiAnnotations::Auto.resolve(CiAnnotations::Github, true),
+            none
+        );
+        assert_eq!(CiAnnotations::Auto.resolve(none, false), none);
+        assert_eq!(
+            "travis"
+                .parse::<CiAnnotations>()
+                .expect_err("invalid annotations are accepted")
+                .to_string(),
+            "CI annotations must be auto, github, gitlab, none (was travis)"
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            strip_ansi_escape_codes("\x1b[0m\x1b[1m\x1b[33mwarning\x1b[0m: text"),
+            "warning: text"
+        );
+        assert_eq!(
+            escape_xml("\x1b[1m<a href=\"x\">'&'</a>\x07\n"),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;\n"
+        );
+    }
+
+    #[test]
+    fn uris() {
+        assert_eq!(
+            file_uri(Path::new("/path with spaces/src/lib.rs")),
+            "file:///path%20with%20spaces/src/lib.rs"
+        );
+        assert_eq!(
+            file_uri(Path::new(r"C:\project\src\lib.rs")),
+            "file:///C:/project/src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn relative_paths() {
+        let workspace_root = Path::new("/workspace");
+        assert_eq!(
+            relative_path(workspace_root, "src/main.rs"),
+            Path::new("src/main.rs")
+        );
+        assert_eq!(
+            relative_path(workspace_root, "/workspace/lib/src/lib.rs"),
+            Path::new("lib/src/lib.rs")
+        );
+        assert_eq!(
+            relative_path(workspace_root, "/elsewhere/src/lib.rs"),
+            Path::new("/elsewhere/src/lib.rs")
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use super::{compiler_messages, escape_xml, primary_span, relative_path};
+use crate::models::Report;
+use cargo_metadata::diagnostic::DiagnosticLevel;
+use itertools::Itertools;
+
+const VERSION: &str = "4.3";
+const INDENT: &str = "  ";
+
+pub fn xml(report: &Report) -> String {
+    let files = compiler_messages(report)
+        .filter_map(|message| {
+            let diagnostic = &message.message;
+            primary_span(diagnostic).map(|span| {
+                let path = relative_path(&report.workspace_root, &span.file_name);
+                (path.to_string_lossy().into_owned(), (diagnostic, span))
+            })
+        })
+        .into_group_map()
+        .into_iter()
+        .sorted_by(|(left, _), (right, _)| left.cmp(right));
+
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_owned(),
+        format!(r#"<checkstyle version="{VERSION}">"#),
+    ];
+    for (path, errors) in files {
+        lines.push(format!(r#"{INDENT}<file name="{}">"#, escape_xml(&path)));
+        for (diagnostic, span) in errors {
+            let source = diagnostic
+                .code
+                .as_ref()
+                .map(|code| format!(r#" source="{}""#, escape_xml(&code.code)))
+                .unwrap_or_default();
+            lines.push(format!(
+                r#"{INDENT}{INDENT}<error line="{}" column="{}" severity="{}" message="{}"{source}/>"#,
+                span.line_start,
+                span.column_start,
+                severity(diagnostic.level),
+                escape_xml(&diagnostic.message),
+            ));
+        }
+        lines.push(format!("{INDENT}</file>"));
+    }
+    lines.push("</checkstyle>".to_owned());
+
+    lines.join("\n") + "\n"
+}
+
+fn severity(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Ice | DiagnosticLevel::Error => "error",
+        DiagnosticLevel::Warning => "warning",
+        _ => "info",
+    }
+}
@@ -0,0 +1,75 @@
+use super::{compiler_messages, repository_path};
+use crate::models::{project_primary_spans, strip_ansi_escape_codes, Report};
+use cargo_metadata::diagnostic::{Diagnostic, DiagnosticLevel, DiagnosticSpan};
+use std::path::Path;
+
+const REPOSITORY_ROOT_VAR: &str = "GITHUB_WORKSPACE";
+
+/// Workflow commands which GitHub Actions turns into annotations.
+pub fn workflow_commands(report: &Report) -> String {
+    compiler_messages(report)
+        .flat_map(|message| {
+            let diagnostic = &message.message;
+            project_primary_spans(diagnostic)
+                .map(move |span| command(diagnostic, &span, &report.workspace_root))
+        })
+        .map(|command| command + "\n")
+        .collect()
+}
+
+fn command(diagnostic: &Diagnostic, span: &DiagnosticSpan, workspace_root: &Path) -> String {
+    let command = match diagnostic.level {
+        DiagnosticLevel::Ice | DiagnosticLevel::Error => "error",
+        DiagnosticLevel::Warning => "warning",
+        _ => "notice",
+    };
+
+    let mut properties = vec![
+        (
+            "file",
+            repository_path(workspace_root, REPOSITORY_ROOT_VAR, &span.file_name),
+        ),
+        ("line", span.line_start.to_string()),
+        ("col", span.column_start.to_string()),
+        ("endLine", span.line_end.to_string()),
+        ("endColumn", span.column_end.to_string()),
+    ];
+    if let Some(code) = &diagnostic.code {
+        properties.push(("title", code.code.clone()));
+    }
+    let properties = properties
+        .into_iter()
+        .map(|(key, value)| format!("{key}={}", escape_property(&value)))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let message = diagnostic
+        .rendered
+        .as_deref()
+        .map_or_else(|| diagnostic.message.clone(), strip_ansi_escape_codes);
+    format!(
+        "::{command} {properties}::{}",
+        escape_data(message.trim_end())
+    )
+}
+
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape_data("50%\r\na:b,c"), "50%25%0D%0Aa:b,c");
+        assert_eq!(escape_property("50%\r\na:b,c"), "50%25%0D%0Aa%3Ab%2Cc");
+    }
+}
@@ -0,0 +1,72 @@
+use super::{compiler_messages, repository_path};
+use crate::models::{project_primary_spans, Report};
+use cargo_metadata::diagnostic::{Diagnostic, DiagnosticLevel, DiagnosticSpan};
+use serde_json::{json, Value};
+use std::path::Path;
+
+const REPOSITORY_ROOT_VAR: &str = "CI_PROJECT_DIR";
+const DEFAULT_CHECK_NAME: &str = "rustc";
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Code Quality report which GitLab shows in merge requests.
+pub fn code_quality(report: &Report) -> Value {
+    let issues = compiler_messages(report)
+        .flat_map(|message| {
+            let diagnostic = &message.message;
+            project_primary_spans(diagnostic)
+                .map(move |span| issue(diagnostic, &span, &report.workspace_root))
+        })
+        .collect::<Vec<_>>();
+    json!(issues)
+}
+
+fn issue(diagnostic: &Diagnostic, span: &DiagnosticSpan, workspace_root: &Path) -> Value {
+    let check_name = diagnostic
+        .code
+        .as_ref()
+        .map_or(DEFAULT_CHECK_NAME, |code| code.code.as_str());
+    let path = repository_path(workspace_root, REPOSITORY_ROOT_VAR, &span.file_name);
+    let fingerprint = fingerprint(&[
+        check_name,
+        &path,
+        &span.line_start.to_string(),
+        &span.column_start.to_string(),
+        &diagnostic.message,
+    ]);
+
+    json!({
+        "type": "issue",
+        "check_name": check_name,
+        "description": diagnostic.message,
+        "severity": severity(diagnostic.level),
+        "fingerprint": fingerprint,
+        "location": {
+            "path": path,
+            "lines": {
+                "begin": span.line_start,
+                "end": span.line_end,
+            },
+        },
+    })
+}
+
+fn severity(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Ice => "blocker",
+        DiagnosticLevel::Error => "critical",
+        DiagnosticLevel::Warning => "minor",
+        _ => "info",
+    }
+}
+
+/// Stable FNV-1a hash, so the same issue keeps its identity between pipelines.
+fn fingerprint(parts: &[&str]) -> String {
+    let hash = parts.iter().fold(FNV_OFFSET_BASIS, |hash, part| {
+        part.bytes().chain([0]).fold(hash, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+    });
+    format!("{hash:016x}")
+}
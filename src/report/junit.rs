@@ -0,0 +1,100 @@
+use super::{compiler_messages, escape_xml, primary_span, relative_path};
+use crate::models::Report;
+use cargo_metadata::{diagnostic::DiagnosticLevel, CompilerMessage};
+use itertools::Itertools;
+
+const INDENT: &str = "  ";
+
+pub fn xml(report: &Report) -> String {
+    let suites = compiler_messages(report)
+        .filter(|i| {
+            matches!(
+                i.message.level,
+                DiagnosticLevel::Ice | DiagnosticLevel::Error | DiagnosticLevel::Warning
+            )
+        })
+        .into_group_map_by(|i| i.target.name.clone())
+        .into_iter()
+        .sorted_by(|(left, _), (right, _)| left.cmp(right))
+        .collect::<Vec<_>>();
+
+    let messages = || suites.iter().flat_map(|(_, messages)| messages);
+    let tests = messages().count();
+    let failures = messages().filter(|i| is_failure(i)).count();
+    let skipped = tests - failures;
+
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_owned(),
+        format!(
+            r#"<testsuites name="{}" tests="{tests}" failures="{failures}" skipped="{skipped}" errors="0" time="{}">"#,
+            env!("CARGO_PKG_NAME"),
+            report.duration_seconds,
+        ),
+    ];
+    for (target_name, messages) in &suites {
+        lines.extend(suite(target_name, messages, report));
+    }
+    lines.push("</testsuites>".to_owned());
+
+    lines.join("\n") + "\n"
+}
+
+fn suite(target_name: &str, messages: &[&CompilerMessage], report: &Report) -> Vec<String> {
+    let tests = messages.len();
+    let failures = messages.iter().filter(|i| is_failure(i)).count();
+    let skipped = tests - failures;
+    let target_name = escape_xml(target_name);
+
+    let mut lines = vec![format!(
+        r#"{INDENT}<testsuite name="{target_name}" tests="{tests}" failures="{failures}" skipped="{skipped}" errors="0">"#
+    )];
+    for message in messages {
+        lines.extend(testcase(&target_name, message, report));
+    }
+    lines.push(format!("{INDENT}</testsuite>"));
+    lines
+}
+
+fn testcase(target_name: &str, message: &CompilerMessage, report: &Report) -> Vec<String> {
+    let diagnostic = &message.message;
+    let text = escape_xml(&diagnostic.message);
+    let rendered = escape_xml(diagnostic.rendered.as_deref().unwrap_or_default());
+    let code = diagnostic.code.as_ref().map(|code| code.code.as_str());
+
+    let (name, location_attributes) = if let Some(span) = primary_span(diagnostic) {
+        let path = relative_path(&report.workspace_root, &span.file_name);
+        let path = escape_xml(&path.to_string_lossy());
+        (
+            format!("{path}:{}:{}: {text}", span.line_start, span.column_start),
+            format!(r#" file="{path}" line="{}""#, span.line_start),
+        )
+    } else {
+        (text.clone(), String::new())
+    };
+
+    let mut lines = vec![format!(
+        r#"{INDENT}{INDENT}<testcase name="{name}" classname="{target_name}"{location_attributes} time="0">"#
+    )];
+    if is_failure(message) {
+        let failure_type = escape_xml(code.unwrap_or("error"));
+        lines.push(format!(
+            r#"{INDENT}{INDENT}{INDENT}<failure type="{failure_type}" message="{text}">{rendered}</failure>"#
+        ));
+    } else {
+        lines.push(format!(
+            r#"{INDENT}{INDENT}{INDENT}<skipped message="{text}"/>"#
+        ));
+        lines.push(format!(
+            "{INDENT}{INDENT}{INDENT}<system-out>{rendered}</system-out>"
+        ));
+    }
+    lines.push(format!("{INDENT}{INDENT}</testcase>"));
+    lines
+}
+
+fn is_failure(message: &CompilerMessage) -> bool {
+    matches!(
+        message.message.level,
+        DiagnosticLevel::Ice | DiagnosticLevel::Error
+    )
+}
@@ -0,0 +1,174 @@
+use super::{compiler_messages, help_uri, primary_span, relative_path};
+use crate::models::{nearest_span, project_primary_spans, strip_ansi_escape_codes, Report};
+use cargo_metadata::diagnostic::{Diagnostic, DiagnosticLevel, DiagnosticSpan, DiagnosticSpanLine};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Reviewdog diagnostic result, with all diagnostics in one document.
+pub fn result(report: &Report) -> Value {
+    json!({
+        "source": source(),
+        "diagnostics": diagnostics(report),
+    })
+}
+
+/// Reviewdog diagnostics, which are written one per line in rdjsonl format.
+pub fn diagnostics(report: &Report) -> Vec<Value> {
+    compiler_messages(report)
+        .filter_map(|message| {
+            let diagnostic = &message.message;
+            primary_span(diagnostic)
+                .map(|span| self::diagnostic(diagnostic, &span, &report.workspace_root))
+        })
+        .collect()
+}
+
+fn source() -> Value {
+    json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "url": env!("CARGO_PKG_REPOSITORY"),
+    })
+}
+
+fn diagnostic(diagnostic: &Diagnostic, span: &DiagnosticSpan, workspace_root: &Path) -> Value {
+    let primary_spans = project_primary_spans(diagnostic).collect::<Vec<_>>();
+    let suggestions = diagnostic
+        .children
+        .iter()
+        .flat_map(|child| &child.spans)
+        .filter(|child_span| nearest_span(child_span, &primary_spans) == Some(span))
+        .filter_map(|child_span| {
+            child_span.suggested_replacement.as_ref().map(|text| {
+                json!({
+                    "range": range(child_span),
+                    "text": text,
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = json!({
+        "message": diagnostic.message,
+        "location": {
+            "path": relative_path(workspace_root, &span.file_name),
+            "range": range(span),
+        },
+        "severity": severity(diagnostic.level),
+        "source": source(),
+    });
+    if let Some(code) = &diagnostic.code {
+        result["code"] = json!({ "value": code.code });
+        if let Some(url) = help_uri(&code.code) {
+            result["code"]["url"] = json!(url);
+        }
+    }
+    if !suggestions.is_empty() {
+        result["suggestions"] = json!(suggestions);
+    }
+    if let Some(rendered) = &diagnostic.rendered {
+        result["original_output"] = json!(strip_ansi_escape_codes(rendered));
+    }
+    result
+}
+
+/// Range with columns counted in UTF-8 bytes, as reviewdog expects.
+fn range(span: &DiagnosticSpan) -> Value {
+    json!({
+        "start": {
+            "line": span.line_start,
+            "column": byte_column(span.text.first(), span.column_start),
+        },
+        "end": {
+            "line": span.line_end,
+            "column": byte_column(span.text.last(), span.column_end),
+        },
+    })
+}
+
+fn byte_column(line: Option<&DiagnosticSpanLine>, column: usize) -> usize {
+    line.map_or(column, |line| {
+        let bytes = line
+            .text
+            .chars()
+            .take(column.saturating_sub(1))
+            .map(char::len_utf8)
+            .sum::<usize>();
+        bytes + 1
+    })
+}
+
+fn severity(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Ice | DiagnosticLevel::Error => "ERROR",
+        DiagnosticLevel::Warning => "WARNING",
+        DiagnosticLevel::FailureNote | DiagnosticLevel::Note | DiagnosticLevel::Help => "INFO",
+        _ => "UNKNOWN_SEVERITY",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn byte_columns() {
+        let line: DiagnosticSpanLine = serde_json::from_value(json!({
+            "text": "let ё = 1;",
+            "highlight_start": 5,
+            "highlight_end": 6,
+        }))
+        .expect("invalid span line");
+        assert_eq!(byte_column(Some(&line), 5), 5);
+        assert_eq!(byte_column(Some(&line), 6), 7);
+        assert_eq!(byte_column(None, 6), 6);
+    }
+
+    #[test]
+    fn suggestions_of_nearest_span() {
+        let span = |line: usize, replacement: Option<&str>| {
+            json!({
+                "file_name": "src/main.rs",
+                "byte_start": 0,
+                "byte_end": 1,
+                "line_start": line,
+                "line_end": line,
+                "column_start": 5,
+                "column_end": 6,
+                "is_primary": true,
+                "text": [],
+                "label": null,
+                "suggested_replacement": replacement,
+                "suggestion_applicability": null,
+                "expansion": null,
+            })
+        };
+        let diagnostic: Diagnostic = serde_json::from_value(json!({
+            "message": "unused variables",
+            "code": null,
+            "level": "warning",
+            "spans": [span(2, None), span(10, None)],
+            "children": [{
+                "message": "prefix them with an underscore",
+                "code": null,
+                "level": "help",
+                "spans": [span(2, Some("_a")), span(10, Some("_b"))],
+                "children": [],
+                "rendered": null,
+            }],
+            "rendered": null,
+        }))
+        .expect("invalid diagnostic");
+
+        let texts = |span: &DiagnosticSpan| {
+            self::diagnostic(&diagnostic, span, Path::new("/workspace"))["suggestions"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|suggestion| suggestion["text"].clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(&diagnostic.spans[0]), vec![json!("_a")]);
+        assert_eq!(texts(&diagnostic.spans[1]), vec![json!("_b")]);
+    }
+}
@@ -0,0 +1,240 @@
+use super::{compiler_messages, encode_path, file_uri, help_uri, relative_path};
+use crate::models::{
+    project_primary_spans, project_secondary_spans, project_suggested_spans, Report,
+};
+use cargo_metadata::diagnostic::{Diagnostic, DiagnosticLevel, DiagnosticSpan};
+use itertools::Itertools;
+use serde_json::{json, Value};
+use std::{iter, path::Path};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const VERSION: &str = "2.1.0";
+const SOURCE_ROOT: &str = "%SRCROOT%";
+
+pub fn log(report: &Report) -> Value {
+    let workspace_root = report.workspace_root.as_path();
+    let diagnostics = compiler_messages(report)
+        .map(|i| &i.message)
+        .collect::<Vec<_>>();
+    let rules = diagnostics
+        .iter()
+        .filter_map(|i| i.code.as_ref())
+        .map(|code| code.code.as_str())
+        .unique()
+        .map(rule)
+        .collect::<Vec<_>>();
+    let results = diagnostics
+        .iter()
+        .map(|diagnostic| result(diagnostic, workspace_root))
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": SCHEMA,
+        "version": VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": {
+                SOURCE_ROOT: { "uri": format!("{}/", file_uri(workspace_root)) },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+fn rule(code: &str) -> Value {
+    let mut rule = json!({ "id": code });
+    if let Some(help_uri) = help_uri(code) {
+        rule["helpUri"] = json!(help_uri);
+    }
+    rule
+}
+
+fn result(diagnostic: &Diagnostic, workspace_root: &Path) -> Value {
+    let locations = project_primary_spans(diagnostic)
+        .map(|span| location(&span, workspace_root))
+        .collect::<Vec<_>>();
+    let related_locations = project_secondary_spans(diagnostic)
+        .enumerate()
+        .map(|(id, span)| {
+            let mut location = location(&span, workspace_root);
+            location["id"] = json!(id);
+            if let Some(label) = &span.label {
+                location["message"] = json!({ "text": label });
+            }
+            location
+        })
+        .collect::<Vec<_>>();
+    let fixes = iter::once(diagnostic)
+        .chain(&diagnostic.children)
+        .filter_map(|i| fix(i, workspace_root))
+        .collect::<Vec<_>>();
+
+    let mut result = json!({
+        "level": level(diagnostic.level),
+        "message": { "text": diagnostic.message },
+        "locations": locations,
+    });
+    if let Some(code) = &diagnostic.code {
+        result["ruleId"] = json!(code.code);
+    }
+    if !related_locations.is_empty() {
+        result["relatedLocations"] = json!(related_locations);
+    }
+    if !fixes.is_empty() {
+        result["fixes"] = json!(fixes);
+    }
+    result
+}
+
+/// Fix made of suggestions of the diagnostic itself, not of its children.
+fn fix(diagnostic: &Diagnostic, workspace_root: &Path) -> Option<Value> {
+    let artifact_changes = project_suggested_spans(&diagnostic.spans)
+        .filter_map(|span| {
+            let replacement = span.suggested_replacement.clone()?;
+            Some((span, replacement))
+        })
+        .into_group_map_by(|(span, _)| span.file_name.clone())
+        .into_iter()
+        .sorted_by(|(left, _), (right, _)| left.cmp(right))
+        .map(|(file_name, replacements)| {
+            let replacements = replacements
+                .into_iter()
+                .map(|(span, replacement)| {
+                    json!({
+                        "deletedRegion": region(&span),
+                        "insertedContent": { "text": replacement },
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "artifactLocation": artifact_location(&file_name, workspace_root),
+                "replacements": replacements,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if artifact_changes.is_empty() {
+        None
+    } else {
+        Some(json!({
+            "description": { "text": diagnostic.message },
+            "artifactChanges": artifact_changes,
+        }))
+    }
+}
+
+fn location(span: &DiagnosticSpan, workspace_root: &Path) -> Value {
+    json!({
+        "physicalLocation": {
+            "artifactLocation": artifact_location(&span.file_name, workspace_root),
+            "region": region(span),
+        },
+    })
+}
+
+fn artifact_location(file_name: &str, workspace_root: &Path) -> Value {
+    let path = relative_path(workspace_root, file_name);
+    if path.is_relative() {
+        json!({ "uri": encode_path(&path), "uriBaseId": SOURCE_ROOT })
+    } else {
+        json!({ "uri": file_uri(&path) })
+    }
+}
+
+fn region(span: &DiagnosticSpan) -> Value {
+    json!({
+        "startLine": span.line_start,
+        "startColumn": span.column_start,
+        "endLine": span.line_end,
+        "endColumn": span.column_end,
+        "byteOffset": span.byte_start,
+        "byteLength": span.byte_end.saturating_sub(span.byte_start),
+    })
+}
+
+fn level(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Ice | DiagnosticLevel::Error => "error",
+        DiagnosticLevel::Warning => "warning",
+        DiagnosticLevel::FailureNote | DiagnosticLevel::Note | DiagnosticLevel::Help => "note",
+        _ => "none",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn fixes() {
+        let span = |file_name: &str, line: usize, replacement: Option<&str>, expansion: Value| {
+            json!({
+                "file_name": file_name,
+                "byte_start": 0,
+                "byte_end": 1,
+                "line_start": line,
+                "line_end": line,
+                "column_start": 5,
+                "column_end": 6,
+                "is_primary": true,
+                "text": [],
+                "label": null,
+                "suggested_replacement": replacement,
+                "suggestion_applicability": replacement.map(|_| "MachineApplicable"),
+                "expansion": expansion,
+            })
+        };
+        let call_site = json!({
+            "span": span("src/main.rs", 7, None, Value::Null),
+            "macro_decl_name": "m!",
+            "def_site_span": null,
+        });
+        let diagnostic: Diagnostic = serde_json::from_value(json!({
+            "message": "unused variable",
+            "code": null,
+            "level": "warning",
+            "spans": [span("/rustc/src/macros.rs", 1, Some("_a"), call_site)],
+            "children": [{
+                "message": "remove it",
+                "code": null,
+                "level": "help",
+                "spans": [span("/registry/lib.rs", 3, Some(""), Value::Null)],
+                "children": [],
+                "rendered": null,
+            }],
+            "rendered": null,
+        }))
+        .expect("invalid diagnostic");
+
+        let result = result(&diagnostic, Path::new("/workspace"));
+        assert_eq!(
+            result["fixes"],
+            json!([{
+                "description": { "text": "unused variable" },
+                "artifactChanges": [{
+                    "artifactLocation": { "uri": "src/main.rs", "uriBaseId": SOURCE_ROOT },
+                    "replacements": [{
+                        "deletedRegion": {
+                            "startLine": 7,
+                            "startColumn": 5,
+                            "endLine": 7,
+                            "endColumn": 6,
+                            "byteOffset": 0,
+                            "byteLength": 1,
+                        },
+                        "insertedContent": { "text": "_a" },
+                    }],
+                }],
+            }])
+        );
+    }
+}
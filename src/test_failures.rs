@@ -0,0 +1,386 @@
+use crate::{
+    io::Buffers,
+    models::{strip_ansi_escape_codes, Location},
+    options::Options,
+};
+use anyhow::Result;
+use itertools::Either;
+use serde_json::Value;
+use std::path::Path;
+
+const FAILURES_HEADER: &str = "failures:";
+const FAILURE_HEADER_PREFIX: &str = "---- ";
+const FAILURE_HEADER_SUFFIX: &str = " stdout ----";
+const RUNNING_PREFIX: &str = "running ";
+const TEST_RESULT_PREFIX: &str = "test result:";
+const PANICKED_AT: &str = " panicked at ";
+const STACK_BACKTRACE: &str = "stack backtrace:";
+
+/// Failed tests parsed from libtest output, either human-readable or JSON one.
+#[derive(Default)]
+pub struct TestFailures {
+    failures: Vec<TestFailure>,
+    section: Section,
+}
+
+#[derive(Debug, PartialEq)]
+struct TestFailure {
+    name: String,
+    /// As printed by libtest.
+    text: String,
+    panic: Option<Panic>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Panic {
+    file_name: String,
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+#[derive(Default, PartialEq)]
+enum Section {
+    #[default]
+    Outside,
+    Failures,
+    Failure,
+}
+
+impl TestFailures {
+    /// Passes the rest of cargo output through, except of test failures, which are withheld.
+    ///
+    /// Unfinished lines, like `test foo ... ` of a running test, are passed through
+    /// as soon as they can't be a part of failures.
+    pub fn parse(buffers: &mut Buffers) -> Result<Self> {
+        let mut result = Self::default();
+        let mut line = Vec::new();
+        let mut written = 0;
+        while let Some(chunk) = buffers.read_child_chunk()? {
+            line.extend_from_slice(&chunk);
+            if line.ends_with(b"\n") {
+                if result.parse_line(&String::from_utf8_lossy(&line)) {
+                    buffers.write_all_to_stdout(&line[written..])?;
+                }
+                line.clear();
+                written = 0;
+            } else if written > 0 || result.passes_through_unfinished(&line) {
+                buffers.write_all_to_stdout(&line[written..])?;
+                written = line.len();
+            }
+        }
+        if !line.is_empty() && result.parse_line(&String::from_utf8_lossy(&line)) {
+            buffers.write_all_to_stdout(&line[written..])?;
+        }
+        result.finish_failure();
+        Ok(result)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Prints failures with the same limit and order as compiler messages.
+    pub fn print(&self, buffers: &mut Buffers, options: &Options) -> Result<()> {
+        let shown = self.shown(options).collect::<Vec<_>>();
+        let human_readable = shown.iter().any(|failure| !failure.is_json());
+        if human_readable {
+            buffers.write_to_stdout(&format!("\n{FAILURES_HEADER}\n\n"))?;
+        }
+
+        let shown_failures = shown.len();
+        let failures = shown.into_iter();
+        let failures = if options.ascending_messages_order {
+            Either::Left(failures)
+        } else {
+            Either::Right(failures.rev())
+        };
+        for failure in failures {
+            buffers.write_to_stdout(&failure.text)?;
+        }
+
+        if options.show_summary && shown_failures < self.failures.len() {
+            let total_failures = self.failures.len();
+            buffers.write_to_stderr(format!(
+                "showing {shown_failures} of {total_failures} test failures\n"
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Panic sites of shown failures, located in project files.
+    pub fn locations(&self, options: &Options, workspace_root: &Path) -> Vec<Location> {
+        self.shown(options)
+            .filter_map(|failure| {
+                let panic = failure.panic.as_ref()?;
+                Path::new(&panic.file_name).is_relative().then(|| {
+                    Location::from_panic(
+                        &panic.file_name,
+                        panic.line,
+                        panic.column,
+                        panic.message.clone(),
+                        &failure.name,
+                        workspace_root,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn shown(&self, options: &Options) -> impl Iterator<Item = &TestFailure> {
+        let limit = if options.limit_messages == 0 {
+            self.failures.len()
+        } else {
+            options.limit_messages
+        };
+        self.failures.iter().take(limit)
+    }
+
+    /// Returns `true` if the unfinished line can't become failures header or JSON failure.
+    fn passes_through_unfinished(&self, line: &[u8]) -> bool {
+        let stripped_line = strip_ansi_escape_codes(&String::from_utf8_lossy(line));
+        self.section == Section::Outside
+            && !stripped_line.starts_with('{')
+            && !FAILURES_HEADER.starts_with(&stripped_line)
+    }
+
+    /// Returns `true` if the line should be passed through.
+    ///
+    /// Captured output of a failed test may contain anything, so its section ends
+    /// only with the next failure header or the final list of failures.
+    fn parse_line(&mut self, line: &str) -> bool {
+        let stripped_line = strip_ansi_escape_codes(line);
+        let stripped_line = stripped_line.trim_end();
+
+        if let Some(failure) = TestFailure::from_json(stripped_line, line) {
+            self.failures.push(failure);
+            return false;
+        }
+
+        match self.section {
+            Section::Outside => {
+                if stripped_line == FAILURES_HEADER {
+                    self.section = Section::Failures;
+                    false
+                } else {
+                    true
+                }
+            },
+            Section::Failures | Section::Failure => {
+                if let Some(name) = stripped_line
+                    .strip_prefix(FAILURE_HEADER_PREFIX)
+                    .and_then(|i| i.strip_suffix(FAILURE_HEADER_SUFFIX))
+                {
+                    self.finish_failure();
+                    self.failures.push(TestFailure {
+                        name: name.to_owned(),
+                        text: line.to_owned(),
+                        panic: None,
+                    });
+                    self.section = Section::Failure;
+                    false
+                } else if stripped_line == FAILURES_HEADER
+                    || self.section == Section::Failures
+                        && (stripped_line.starts_with(TEST_RESULT_PREFIX)
+                            || stripped_line.starts_with(RUNNING_PREFIX))
+                {
+                    self.finish_failure();
+                    self.section = Section::Outside;
+                    true
+                } else {
+                    if self.section == Section::Failure {
+                        if let Some(failure) = self.failures.last_mut() {
+                            failure.text.push_str(line);
+                        }
+                    }
+                    false
+                }
+            },
+        }
+    }
+
+    fn finish_failure(&mut self) {
+        if self.section == Section::Failure {
+            if let Some(failure) = self.failures.last_mut() {
+                let text = strip_ansi_escape_codes(&failure.text);
+                failure.panic = Panic::parse(&text);
+            }
+            self.section = Section::Failures;
+        }
+    }
+}
+
+impl TestFailure {
+    /// Parses `{ "type": "test", "event": "failed", ... }` of `-Z unstable-options --format json`.
+    fn from_json(stripped_line: &str, line: &str) -> Option<Self> {
+        if !stripped_line.starts_with('{') {
+            return None;
+        }
+        let value = serde_json::from_str::<Value>(stripped_line).ok()?;
+        if value["type"] != "test" || value["event"] != "failed" {
+            return None;
+        }
+        let name = value["name"].as_str()?.to_owned();
+        let panic = value["stdout"].as_str().and_then(Panic::parse);
+        Some(Self {
+            name,
+            text: line.to_owned(),
+            panic,
+        })
+    }
+
+    fn is_json(&self) -> bool {
+        self.text.starts_with('{')
+    }
+}
+
+impl Panic {
+    /// Supports both `panicked at src/lib.rs:1:2:\nmessage`
+    /// and older `panicked at 'message', src/lib.rs:1:2` formats.
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let (_, panicked_at) = lines.find_map(|line| line.split_once(PANICKED_AT))?;
+        if let Some(message_and_location) = panicked_at.strip_prefix('\'') {
+            let (message, location) = message_and_location.rsplit_once("', ")?;
+            Self::new(location, message.to_owned())
+        } else {
+            let location = panicked_at.strip_suffix(':').unwrap_or(panicked_at);
+            let message = lines
+                .take_while(|line| {
+                    !line.is_empty()
+                        && !line.starts_with("note: ")
+                        && !line.starts_with(STACK_BACKTRACE)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Self::new(location, message)
+        }
+    }
+
+    fn new(location: &str, message: String) -> Option<Self> {
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next()?.parse().ok()?;
+        let line = parts.next()?.parse().ok()?;
+        let file_name = parts.next()?.to_owned();
+        Some(Self {
+            file_name,
+            line,
+            column,
+            message,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn human_readable_output() {
+        let output = "\
+running 2 tests
+test tests::bad ... FAILED
+test tests::good ... ok
+
+failures:
+
+---- tests::bad stdout ----
+running subprocess
+test result: captured too
+thread 'tests::bad' panicked at src/lib.rs:10:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+stack backtrace:
+   0: rust_begin_unwind
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::bad
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+";
+        let mut failures = TestFailures::default();
+        let passed_through = output
+            .split_inclusive('\n')
+            .filter(|line| failures.parse_line(line))
+            .collect::<String>();
+        failures.finish_failure();
+
+        assert_eq!(
+            passed_through,
+            "\
+running 2 tests
+test tests::bad ... FAILED
+test tests::good ... ok
+
+failures:
+    tests::bad
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+"
+        );
+        assert_eq!(failures.failures.len(), 1);
+        assert_eq!(failures.failures[0].name, "tests::bad");
+        assert!(failures.failures[0].text.starts_with(
+            "---- tests::bad stdout ----\nrunning subprocess\ntest result: captured too\n"
+        ));
+        assert_eq!(
+            failures.failures[0].panic,
+            Some(Panic {
+                file_name: "src/lib.rs".to_owned(),
+                line: 10,
+                column: 9,
+                message: "assertion `left == right` failed\n  left: 1\n right: 2".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn old_panic_format() {
+        assert_eq!(
+            Panic::parse("thread 'main' panicked at 'oops, again', tests/it.rs:3:5\nnote: ..."),
+            Some(Panic {
+                file_name: "tests/it.rs".to_owned(),
+                line: 3,
+                column: 5,
+                message: "oops, again".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn json_output() {
+        let line = r#"{ "type": "test", "name": "tests::bad", "event": "failed", "stdout": "thread 'tests::bad' panicked at src/lib.rs:4:5:\nexplicit panic\n" }"#;
+        let mut failures = TestFailures::default();
+        assert!(failures.parse_line(r#"{ "type": "suite", "event": "started", "test_count": 1 }"#));
+        assert!(!failures.parse_line(line));
+        assert_eq!(
+            failures.failures,
+            vec![TestFailure {
+                name: "tests::bad".to_owned(),
+                text: line.to_owned(),
+                panic: Some(Panic {
+                    file_name: "src/lib.rs".to_owned(),
+                    line: 4,
+                    column: 5,
+                    message: "explicit panic".to_owned(),
+                }),
+            }]
+        );
+    }
+
+    #[test]
+    fn unfinished_lines() {
+        let mut failures = TestFailures::default();
+        assert!(failures.passes_through_unfinished(b"test tests::slow ... "));
+        assert!(!failures.passes_through_unfinished(b"fail"));
+        assert!(!failures.passes_through_unfinished(br#"{ "type": "test""#));
+        assert!(!failures.parse_line("failures:\n"));
+        assert!(!failures.passes_through_unfinished(b"test tests::slow ... "));
+    }
+}
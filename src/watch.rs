@@ -0,0 +1,104 @@
+use crate::{process::CargoProcess, workspace::Workspace};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+const MANIFEST_FILE_NAME: &str = "Cargo.toml";
+const SOURCE_FILE_EXTENSION: &str = "rs";
+
+pub struct Changes {
+    receiver: Receiver<()>,
+}
+
+type Snapshot = HashMap<PathBuf, SystemTime>;
+
+impl Changes {
+    /// Polls workspace sources and kills in-flight build after each (debounced) change.
+    pub fn watch(workspace: &Workspace) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let root = workspace.root().to_path_buf();
+        let target_directory = workspace.target_directory().to_path_buf();
+
+        thread::spawn(move || {
+            let mut snapshot = take_snapshot(&root, &target_directory);
+            let mut last_change: Option<Instant> = None;
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                let new_snapshot = take_snapshot(&root, &target_directory);
+                if new_snapshot != snapshot {
+                    snapshot = new_snapshot;
+                    last_change = Some(Instant::now());
+                } else if let Some(time) = last_change {
+                    if time.elapsed() >= DEBOUNCE_INTERVAL {
+                        last_change = None;
+                        CargoProcess::kill_current();
+                        if sender.send(()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+
+    /// Blocks until next change. Returns `false` if interrupted.
+    pub fn wait(&self) -> bool {
+        loop {
+            if CargoProcess::interrupted() {
+                break false;
+            }
+            match self.receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(()) => {
+                    while self.receiver.try_recv().is_ok() {}
+                    break true;
+                },
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break false,
+            }
+        }
+    }
+}
+
+fn take_snapshot(root: &Path, target_directory: &Path) -> Snapshot {
+    let mut result = Snapshot::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if file_type.is_dir() {
+                if !is_hidden && path != target_directory {
+                    dirs.push(path);
+                }
+            } else if is_watched_file(&path) {
+                if let Ok(modified) = fs::metadata(&path).and_then(|i| i.modified()) {
+                    let _ = result.insert(path, modified);
+                }
+            }
+        }
+    }
+    result
+}
+
+fn is_watched_file(path: &Path) -> bool {
+    path.file_name()
+        .map_or(false, |name| name == MANIFEST_FILE_NAME)
+        || path
+            .extension()
+            .map_or(false, |extension| extension == SOURCE_FILE_EXTENSION)
+}
@@ -0,0 +1,177 @@
+use crate::messages::CUSTOM_BUILD_KIND;
+use anyhow::Result;
+use cargo_metadata::{Metadata, MetadataCommand, Package, PackageId};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+const PATH_SOURCE_PREFIX: &str = "path+";
+
+pub struct Workspace {
+    root: PathBuf,
+    target_directory: PathBuf,
+    members: HashSet<PackageId>,
+    total_units: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageKind {
+    Member,
+    PathDependency,
+    RemoteDependency,
+}
+
+impl Workspace {
+    /// Dependencies are resolved offline, only to estimate the build progress,
+    /// so they are skipped if it's impossible.
+    pub fn from_metadata() -> Result<Self> {
+        let (metadata, total_units) = match MetadataCommand::new()
+            .other_options(vec!["--offline".to_owned()])
+            .exec()
+        {
+            Ok(metadata) => {
+                let total_units = count_units(&metadata);
+                (metadata, Some(total_units))
+            },
+            Err(_) => (MetadataCommand::new().no_deps().exec()?, None),
+        };
+        let mut result = Self::new(
+            metadata.workspace_root.into(),
+            metadata.target_directory.into(),
+            metadata.workspace_members,
+        );
+        result.total_units = total_units;
+        Ok(result)
+    }
+
+    pub fn new(
+        root: PathBuf,
+        target_directory: PathBuf,
+        members: impl IntoIterator<Item = PackageId>,
+    ) -> Self {
+        Self {
+            root,
+            target_directory,
+            members: members.into_iter().collect(),
+            total_units: None,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn target_directory(&self) -> &Path {
+        &self.target_directory
+    }
+
+    /// Estimated number of artifact and build script messages of a full build.
+    pub fn total_units(&self) -> Option<usize> {
+        self.total_units
+    }
+
+    pub fn package_kind(&self, package_id: &PackageId) -> PackageKind {
+        if self.members.contains(package_id) {
+            PackageKind::Member
+        } else if Self::has_path_source(package_id) {
+            PackageKind::PathDependency
+        } else {
+            PackageKind::RemoteDependency
+        }
+    }
+
+    fn has_path_source(package_id: &PackageId) -> bool {
+        // Both "name version (path+file:///...)" and "path+file:///...#name@version" formats
+        // are produced, depending on cargo version.
+        let repr = package_id.repr.as_str();
+        repr.starts_with(PATH_SOURCE_PREFIX) || repr.contains(&format!("({PATH_SOURCE_PREFIX}"))
+    }
+}
+
+/// Estimates how many artifact and build script messages cargo will produce.
+fn count_units(metadata: &Metadata) -> usize {
+    let members = metadata.workspace_members.iter().collect::<HashSet<_>>();
+    let resolved = match &metadata.resolve {
+        Some(resolve) => resolve.nodes.iter().map(|node| &node.id).collect(),
+        None => members.clone(),
+    };
+
+    metadata
+        .packages
+        .iter()
+        .filter(|package| resolved.contains(&package.id))
+        .map(|package| package_units(package, members.contains(&package.id)))
+        .sum()
+}
+
+/// Build script is compiled and then executed, members' binaries are compiled besides libraries.
+fn package_units(package: &Package, is_member: bool) -> usize {
+    let build_script_units = package
+        .targets
+        .iter()
+        .filter(|target| target.kind.iter().any(|kind| kind == CUSTOM_BUILD_KIND))
+        .count()
+        * 2;
+    let compiled_units = if is_member {
+        package
+            .targets
+            .iter()
+            .filter(|target| {
+                target
+                    .kind
+                    .iter()
+                    .any(|kind| kind.ends_with("lib") || kind == "bin")
+            })
+            .count()
+    } else {
+        1
+    };
+    build_script_units + compiled_units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn package_kinds() {
+        let package_id = |repr: &str| PackageId {
+            repr: repr.to_owned(),
+        };
+        let member = package_id("path+file:///workspace/app#0.1.0");
+        let old_format_member = package_id("app 0.1.0 (path+file:///workspace/app)");
+        let workspace = Workspace::new(
+            PathBuf::from("/workspace"),
+            PathBuf::from("/workspace/target"),
+            [member.clone(), old_format_member.clone()],
+        );
+
+        assert_eq!(workspace.package_kind(&member), PackageKind::Member);
+        assert_eq!(
+            workspace.package_kind(&old_format_member),
+            PackageKind::Member
+        );
+        assert_eq!(
+            workspace.package_kind(&package_id("path+file:///workspace/vendored/lib#0.1.0")),
+            PackageKind::PathDependency
+        );
+        assert_eq!(
+            workspace.package_kind(&package_id("lib 0.1.0 (path+file:///elsewhere/lib)")),
+            PackageKind::PathDependency
+        );
+        assert_eq!(
+            workspace.package_kind(&package_id(
+                "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0"
+            )),
+            PackageKind::RemoteDependency
+        );
+        assert_eq!(
+            workspace.package_kind(&package_id(
+                "git+https://github.com/user/lib?branch=main#0123456789abcdef"
+            )),
+            PackageKind::RemoteDependency
+        );
+    }
+}